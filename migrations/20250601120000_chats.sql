@@ -0,0 +1,8 @@
+-- Per-chat settings.
+CREATE TABLE chats
+(
+    id        INTEGER PRIMARY KEY NOT NULL,
+
+    -- Whether the subscription notifications are paused.
+    is_paused INTEGER             NOT NULL DEFAULT FALSE
+) STRICT;
@@ -0,0 +1,16 @@
+-- Chats authorized by redeeming an invite.
+CREATE TABLE authorized_chats
+(
+    chat_id       INTEGER PRIMARY KEY NOT NULL,
+    authorized_at TEXT                NOT NULL
+) STRICT;
+
+-- Invites generated by admins.
+CREATE TABLE invites
+(
+    token       BLOB PRIMARY KEY NOT NULL,
+    expires_at  TEXT             NOT NULL,
+
+    -- Reusable invites stay valid until they expire, otherwise an invite is deleted once redeemed.
+    is_reusable INTEGER          NOT NULL
+) STRICT, WITHOUT ROWID;
@@ -0,0 +1,5 @@
+-- Timestamp of the notification, used to limit the number of notifications per chat.
+-- It is `NULL` for notifications sent before the migration.
+ALTER TABLE notifications ADD COLUMN notified_at TEXT NULL;
+
+CREATE INDEX notifications_chat_id_notified_at ON notifications (chat_id, notified_at);
@@ -0,0 +1,16 @@
+-- Cached Vinted brand and size name lookups.
+CREATE TABLE vinted_lookups
+(
+    -- `brand` or `size`.
+    kind       TEXT NOT NULL,
+
+    -- Lowercase name from the search query.
+    name       TEXT NOT NULL,
+
+    -- Comma-separated IDs, empty if nothing is found.
+    ids        TEXT NOT NULL,
+
+    updated_at TEXT NOT NULL,
+
+    PRIMARY KEY (kind, name)
+) STRICT, WITHOUT ROWID;
@@ -0,0 +1,10 @@
+-- Cached item details, so that each item is fetched at most once.
+CREATE TABLE item_details
+(
+    item_id    TEXT PRIMARY KEY NOT NULL REFERENCES items (id) ON UPDATE CASCADE ON DELETE CASCADE,
+
+    -- JSON-serialized details.
+    details    TEXT             NOT NULL,
+
+    fetched_at TEXT             NOT NULL
+) STRICT;
@@ -0,0 +1,2 @@
+-- Whether to send all the item photos as an album, or only the main one.
+ALTER TABLE chats ADD COLUMN sends_album INTEGER NOT NULL DEFAULT TRUE;
@@ -0,0 +1,13 @@
+-- Per-chat blocklist of the sellers, whose items are never shown.
+CREATE TABLE blocked_sellers
+(
+    chat_id        INTEGER NOT NULL,
+    marketplace_id TEXT    NOT NULL,
+
+    -- Marketplace-specific seller ID, or the username if the ID is unknown.
+    seller_id      TEXT    NOT NULL,
+
+    blocked_at     TEXT    NOT NULL,
+
+    PRIMARY KEY (chat_id, marketplace_id, seller_id)
+) STRICT, WITHOUT ROWID;
@@ -0,0 +1,18 @@
+-- Fuzzy item fingerprints to detect the same item listed several times.
+CREATE TABLE item_fingerprints
+(
+    item_id         TEXT PRIMARY KEY NOT NULL REFERENCES items (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    marketplace_id  TEXT             NOT NULL,
+
+    -- Lowercase sorted unique words of the title.
+    title           TEXT             NOT NULL,
+
+    -- Normalised asking price, if any.
+    price           TEXT             NULL,
+
+    seller_id       TEXT             NOT NULL,
+    seller_username TEXT             NOT NULL,
+
+    -- Difference hash of the main picture.
+    picture_hash    INTEGER          NULL
+) STRICT;
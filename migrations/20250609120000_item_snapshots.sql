@@ -0,0 +1,21 @@
+-- Normalised item snapshots, updated on each sighting.
+-- The columns are `NULL` for the items seen before the migration.
+ALTER TABLE items ADD COLUMN marketplace_id TEXT NULL;
+ALTER TABLE items ADD COLUMN url TEXT NULL;
+ALTER TABLE items ADD COLUMN title TEXT NULL;
+ALTER TABLE items ADD COLUMN description TEXT NULL;
+
+-- See `Price::kind()`.
+ALTER TABLE items ADD COLUMN price_kind TEXT NULL;
+ALTER TABLE items ADD COLUMN price_amount TEXT NULL;
+
+ALTER TABLE items ADD COLUMN seller_id TEXT NULL;
+ALTER TABLE items ADD COLUMN seller_username TEXT NULL;
+ALTER TABLE items ADD COLUMN seller_url TEXT NULL;
+ALTER TABLE items ADD COLUMN location TEXT NULL;
+
+-- See `Condition::as_str()`.
+ALTER TABLE items ADD COLUMN condition TEXT NULL;
+
+ALTER TABLE items ADD COLUMN picture_url TEXT NULL;
+ALTER TABLE items ADD COLUMN first_seen_at TEXT NULL;
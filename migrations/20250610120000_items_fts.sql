@@ -0,0 +1,32 @@
+-- Full-text index over the item snapshots, used by `/history`.
+--
+-- The item's `rowid` is not stable for a `TEXT` primary key, hence the explicit unindexed item ID.
+CREATE VIRTUAL TABLE items_fts USING fts5
+(
+    item_id UNINDEXED,
+    title,
+    description,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO items_fts (item_id, title, description)
+SELECT id, title, description FROM items WHERE title IS NOT NULL;
+
+CREATE TRIGGER items_fts_insert AFTER INSERT ON items WHEN new.title IS NOT NULL
+BEGIN
+    INSERT INTO items_fts (item_id, title, description) VALUES (new.id, new.title, new.description);
+END;
+
+-- Only reindex when the text actually changes, since each sighting updates the item.
+CREATE TRIGGER items_fts_update AFTER UPDATE OF title, description ON items
+    WHEN old.title IS NOT new.title OR old.description IS NOT new.description
+BEGIN
+    DELETE FROM items_fts WHERE item_id = old.id;
+    INSERT INTO items_fts (item_id, title, description)
+    SELECT new.id, new.title, new.description WHERE new.title IS NOT NULL;
+END;
+
+CREATE TRIGGER items_fts_delete AFTER DELETE ON items
+BEGIN
+    DELETE FROM items_fts WHERE item_id = old.id;
+END;
@@ -0,0 +1,24 @@
+-- Pending item notifications, which are recorded together with `notifications`
+-- and delivered by the outbox sender.
+CREATE TABLE outbox
+(
+    item_id         TEXT    NOT NULL REFERENCES items (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    chat_id         INTEGER NOT NULL,
+
+    -- Rendered HTML text.
+    text            TEXT    NOT NULL,
+
+    -- JSON array of the picture URLs.
+    picture_urls    TEXT    NOT NULL,
+
+    n_attempts      INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT    NOT NULL,
+
+    -- Set right before the delivery attempt. An entry left in flight may have been delivered,
+    -- so it is dropped on the next start instead of being retried.
+    is_in_flight    INTEGER NOT NULL DEFAULT FALSE,
+
+    PRIMARY KEY (item_id, chat_id)
+) STRICT;
+
+CREATE INDEX outbox_next_attempt_at ON outbox (next_attempt_at) WHERE NOT is_in_flight;
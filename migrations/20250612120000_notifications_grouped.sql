@@ -0,0 +1,3 @@
+-- Item, which notification the duplicate was grouped into. `NULL` for the notified items themselves.
+-- The grouped duplicates are not counted against the notification quota.
+ALTER TABLE notifications ADD COLUMN grouped_into TEXT NULL;
@@ -0,0 +1,18 @@
+-- Send only the main photo by default. SQLite cannot alter the column default, so the table is rebuilt.
+-- The album setting is reset for the existing chats too, because they could not tell the default from their choice.
+CREATE TABLE chats_new
+(
+    id          INTEGER PRIMARY KEY NOT NULL,
+
+    -- Whether the subscription notifications are paused.
+    is_paused   INTEGER             NOT NULL DEFAULT FALSE,
+
+    -- Whether to send all the item photos as an album, or only the main one.
+    sends_album INTEGER             NOT NULL DEFAULT FALSE
+) STRICT;
+
+INSERT INTO chats_new (id, is_paused, sends_album) SELECT id, is_paused, FALSE FROM chats;
+
+DROP TABLE chats;
+
+ALTER TABLE chats_new RENAME TO chats;
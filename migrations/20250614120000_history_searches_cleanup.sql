@@ -0,0 +1,2 @@
+-- The history search text is now passed in the command payload, drop the stored texts.
+DELETE FROM key_values WHERE key LIKE 'history::%';
@@ -0,0 +1,34 @@
+-- Allow plain messages, which are not tied to an item, in the outbox.
+-- SQLite cannot drop `NOT NULL` in place, hence the table is rebuilt.
+CREATE TABLE outbox_new
+(
+    id              INTEGER PRIMARY KEY,
+
+    -- `NULL` for a plain message.
+    item_id         TEXT    NULL REFERENCES items (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    chat_id         INTEGER NOT NULL,
+
+    -- Rendered HTML text.
+    text            TEXT    NOT NULL,
+
+    -- JSON array of the picture URLs.
+    picture_urls    TEXT    NOT NULL,
+
+    n_attempts      INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT    NOT NULL,
+
+    -- Set right before the delivery attempt. An entry left in flight may have been delivered,
+    -- so it is dropped on the next start instead of being retried.
+    is_in_flight    INTEGER NOT NULL DEFAULT FALSE,
+
+    UNIQUE (item_id, chat_id)
+) STRICT;
+
+INSERT INTO outbox_new (item_id, chat_id, text, picture_urls, n_attempts, next_attempt_at, is_in_flight)
+SELECT item_id, chat_id, text, picture_urls, n_attempts, next_attempt_at, is_in_flight
+FROM outbox;
+
+DROP TABLE outbox;
+ALTER TABLE outbox_new RENAME TO outbox;
+
+CREATE INDEX outbox_next_attempt_at ON outbox (next_attempt_at) WHERE NOT is_in_flight;
@@ -0,0 +1,22 @@
+-- Vinted brand and size IDs differ between the domains, so the lookups are cached per domain.
+-- The existing entries are of an unknown domain, and the cache is simply rebuilt.
+DROP TABLE vinted_lookups;
+
+CREATE TABLE vinted_lookups
+(
+    -- Vinted top-level domain, for example `nl` or `co.uk`.
+    domain     TEXT NOT NULL,
+
+    -- `brand` or `size`.
+    kind       TEXT NOT NULL,
+
+    -- Lowercase name from the search query.
+    name       TEXT NOT NULL,
+
+    -- Comma-separated IDs, empty if nothing is found.
+    ids        TEXT NOT NULL,
+
+    updated_at TEXT NOT NULL,
+
+    PRIMARY KEY (domain, kind, name)
+) STRICT, WITHOUT ROWID;
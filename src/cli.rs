@@ -1,9 +1,11 @@
-use std::path::PathBuf;
+use std::{net::SocketAddr, path::PathBuf};
 
 use clap::{Parser, Subcommand};
 use secrecy::SecretString;
 use url::Url;
 
+use crate::{marketplace::VintedDomain, prelude::*, quotas::Quotas};
+
 #[derive(Parser)]
 #[command(author, version, about, long_about, propagate_version = true)]
 pub struct Args {
@@ -33,15 +35,131 @@ pub enum Command {
     /// Run the bot indefinitely.
     Run(Box<RunArgs>),
 
+    /// Search once and print the items, including the ones the query post-filter dropped.
+    Search(Box<SearchArgs>),
+
     /// Manage Vinted settings.
     Vinted {
         #[command(subcommand)]
         command: VintedCommand,
     },
+
+    /// Manage the chat subscriptions, bypassing the quotas.
+    Subscriptions {
+        #[command(subcommand)]
+        command: SubscriptionsCommand,
+    },
+
+    /// Maintain the database.
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SubscriptionsCommand {
+    /// List the subscriptions.
+    #[clap(visible_alias = "ls")]
+    List {
+        /// Only list the subscriptions of the chat.
+        #[clap(long = "chat-id", allow_negative_numbers = true)]
+        chat_id: Option<i64>,
+
+        #[clap(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// Subscribe the chat to the search queries.
+    Add {
+        #[clap(long = "chat-id", allow_negative_numbers = true)]
+        chat_id: i64,
+
+        /// Search queries, each one is a separate argument.
+        #[clap(required = true)]
+        queries: Vec<String>,
+    },
+
+    /// Unsubscribe the chat from the search queries.
+    #[clap(visible_alias = "rm")]
+    Remove {
+        #[clap(long = "chat-id", allow_negative_numbers = true)]
+        chat_id: i64,
+
+        /// Search queries, each one is a separate argument.
+        #[clap(required = true)]
+        queries: Vec<String>,
+    },
+
+    /// Move the subscriptions to another chat.
+    #[clap(visible_alias = "mv")]
+    Move {
+        /// Chat to move the subscriptions from.
+        #[clap(long = "chat-id", allow_negative_numbers = true)]
+        chat_id: i64,
+
+        /// Chat to move the subscriptions to.
+        #[clap(long = "to-chat-id", allow_negative_numbers = true)]
+        to_chat_id: i64,
+
+        /// Search queries to move, all the subscriptions by default.
+        queries: Vec<String>,
+    },
+}
+
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum DbCommand {
+    /// Delete the items, which have not been seen for the retention period.
+    Prune {
+        /// Delete the items, which have not been seen for this number of days.
+        #[clap(
+            long = "retention-days",
+            env = "RETENTION_DAYS",
+            hide_env_values = true,
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        retention_days: u32,
+
+        /// Only print what would be deleted.
+        #[clap(long = "dry-run")]
+        dry_run: bool,
+    },
+
+    /// Export the search queries, subscriptions, chat settings and notification history as JSON.
+    Export {
+        /// Output file, standard output by default.
+        path: Option<PathBuf>,
+    },
+
+    /// Import the JSON export, merging it into the database.
+    Import {
+        /// Input file, standard input by default.
+        path: Option<PathBuf>,
+    },
+
+    /// Write a consistent copy of the database, which is safe to do while the bot is running.
+    Backup {
+        /// Path of the new backup file, it must not exist.
+        path: PathBuf,
+    },
 }
 
 #[derive(Parser)]
 pub struct RunArgs {
+    /// TOML configuration file, the keys are the long argument names.
+    ///
+    /// Command-line arguments and environment variables take precedence over the file.
+    /// The search interval, duplicate window, quotas, and chat IDs are reloaded
+    /// on `SIGHUP` or when the file changes.
+    #[clap(long, env = "CONFIG", hide_env_values = true)]
+    pub config: Option<PathBuf>,
+
     /// Search interval, in seconds.
     #[clap(
         long = "search-interval-secs",
@@ -51,14 +169,116 @@ pub struct RunArgs {
     )]
     pub search_interval_secs: u64,
 
+    /// Suppress notifications about the items, which look like the items the chat
+    /// was notified about within this number of hours.
+    #[clap(
+        long = "duplicate-window-hours",
+        env = "DUPLICATE_WINDOW_HOURS",
+        default_value = "168",
+        hide_env_values = true
+    )]
+    pub duplicate_window_hours: i64,
+
+    /// Periodically delete the items, which have not been seen for this number of days,
+    /// along with their notifications. By default, the items are kept forever.
+    ///
+    /// Must cover the duplicate window, otherwise the notified items would be forgotten too early.
+    #[clap(
+        long = "retention-days",
+        env = "RETENTION_DAYS",
+        hide_env_values = true,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub retention_days: Option<u32>,
+
+    #[command(flatten)]
+    pub quotas: QuotaArgs,
+
     #[command(flatten)]
     pub telegram: TelegramArgs,
 
+    #[command(flatten)]
+    pub marketplaces: MarketplaceArgs,
+}
+
+#[derive(Parser)]
+pub struct SearchArgs {
+    /// Search query, the same as the bot accepts.
+    pub query: String,
+
+    /// Only search on these marketplaces, even if they are disabled.
+    /// By default, search on the enabled marketplaces, which the query allows.
+    #[clap(long = "marketplace")]
+    pub marketplace_ids: Vec<String>,
+
+    /// Limit of the printed items per marketplace.
+    #[clap(long)]
+    pub limit: Option<usize>,
+
+    /// Print JSON instead of the human-readable report.
+    #[clap(long)]
+    pub json: bool,
+
+    #[command(flatten)]
+    pub marketplaces: MarketplaceArgs,
+}
+
+#[derive(Parser)]
+pub struct MarketplaceArgs {
     #[command(flatten)]
     pub marktplaats: MarktplaatsArgs,
 
+    #[command(flatten)]
+    pub tweedehands: TweedeHandsArgs,
+
     #[command(flatten)]
     pub vinted: VintedArgs,
+
+    #[command(flatten)]
+    pub kleinanzeigen: KleinanzeigenArgs,
+}
+
+#[derive(Parser)]
+#[clap(next_help_heading = "Quotas")]
+pub struct QuotaArgs {
+    /// Maximum number of subscriptions per chat, unlimited by default.
+    #[clap(
+        long = "max-subscriptions-per-chat",
+        env = "MAX_SUBSCRIPTIONS_PER_CHAT",
+        hide_env_values = true
+    )]
+    pub max_subscriptions_per_chat: Option<u32>,
+
+    /// Maximum number of notifications per chat per hour, unlimited by default.
+    #[clap(
+        long = "max-notifications-per-hour",
+        env = "MAX_NOTIFICATIONS_PER_HOUR",
+        hide_env_values = true
+    )]
+    pub max_notifications_per_hour: Option<u32>,
+}
+
+impl RunArgs {
+    /// Check the constraints between the arguments, which `clap` cannot express.
+    pub fn validate(&self) -> Result {
+        if let Some(retention_days) = self.retention_days {
+            ensure!(
+                i64::from(retention_days) * 24 >= self.duplicate_window_hours,
+                "the retention period ({retention_days} days) must not be shorter than the duplicate window ({} hours)",
+                self.duplicate_window_hours,
+            );
+        }
+        Ok(())
+    }
+}
+
+impl From<&QuotaArgs> for Quotas {
+    fn from(args: &QuotaArgs) -> Self {
+        Self {
+            max_subscriptions: args.max_subscriptions_per_chat,
+            max_notifications_per_hour: args.max_notifications_per_hour,
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -68,11 +288,19 @@ pub enum VintedCommand {
     Authenticate {
         /// Vinted refresh token.
         refresh_token: SecretString,
+
+        /// Vinted domain which the token belongs to.
+        #[clap(long, default_value = "nl")]
+        domain: VintedDomain,
     },
 
     /// Print the stored authentication tokens.
     #[clap(visible_alias = "tokens")]
-    ShowTokens,
+    ShowTokens {
+        /// Vinted domain which the tokens belong to.
+        #[clap(long, default_value = "nl")]
+        domain: VintedDomain,
+    },
 }
 
 #[derive(Parser)]
@@ -105,6 +333,56 @@ pub struct MarktplaatsArgs {
     pub search_in_title_and_description: bool,
 }
 
+#[derive(Parser)]
+#[clap(next_help_heading = "2dehands")]
+pub struct TweedeHandsArgs {
+    /// Enable 2dehands.be search.
+    #[clap(
+        long = "2dehands-enabled",
+        env = "TWEEDEHANDS_ENABLED",
+        id = "tweedehands_enabled",
+        hide_env_values = true
+    )]
+    pub enabled: bool,
+
+    /// Link to the French-speaking 2ememain.be instead of 2dehands.be.
+    #[clap(
+        long = "2dehands-french",
+        env = "TWEEDEHANDS_FRENCH",
+        id = "tweedehands_french",
+        hide_env_values = true
+    )]
+    pub french: bool,
+
+    /// Limit of 2dehands search results per query.
+    #[clap(
+        long = "2dehands-search-limit",
+        env = "TWEEDEHANDS_SEARCH_LIMIT",
+        id = "tweedehands_search_limit",
+        default_value = "10",
+        hide_env_values = true
+    )]
+    pub search_limit: u32,
+
+    /// Heartbeat URL for the 2dehands connection.
+    #[clap(
+        long = "2dehands-heartbeat-url",
+        env = "TWEEDEHANDS_HEARTBEAT_URL",
+        id = "tweedehands_heartbeat_url",
+        hide_env_values = true
+    )]
+    pub heartbeat_url: Option<Url>,
+
+    /// Enable search in descriptions for 2dehands.
+    #[clap(
+        long = "2dehands-search-in-title-and-description",
+        env = "TWEEDEHANDS_SEARCH_IN_TITLE_AND_DESCRIPTION",
+        id = "tweedehands_search_in_title_and_description",
+        hide_env_values = true
+    )]
+    pub search_in_title_and_description: bool,
+}
+
 #[derive(Parser)]
 #[clap(next_help_heading = "Vinted")]
 pub struct VintedArgs {
@@ -117,6 +395,19 @@ pub struct VintedArgs {
     )]
     pub vinted_search_limit: u32,
 
+    /// Vinted country domains to search on, for example `nl`, `be`, `de` or `fr`.
+    ///
+    /// Each domain requires its own `vinted authenticate --domain`.
+    #[clap(
+        long = "vinted-domain",
+        env = "VINTED_DOMAINS",
+        value_delimiter = ',',
+        num_args = 1..,
+        default_value = "nl",
+        hide_env_values = true
+    )]
+    pub domains: Vec<VintedDomain>,
+
     /// Heartbeat URL for the Vinted connection.
     #[clap(
         long = "vinted-heartbeat-url",
@@ -127,6 +418,40 @@ pub struct VintedArgs {
     pub heartbeat_url: Option<Url>,
 }
 
+#[derive(Parser)]
+#[clap(next_help_heading = "Kleinanzeigen")]
+pub struct KleinanzeigenArgs {
+    /// Enable kleinanzeigen.de search.
+    #[clap(
+        long = "kleinanzeigen-enabled",
+        env = "KLEINANZEIGEN_ENABLED",
+        id = "kleinanzeigen_enabled",
+        hide_env_values = true
+    )]
+    pub enabled: bool,
+
+    /// Limit of Kleinanzeigen search results per query.
+    ///
+    /// Every result costs an extra request to fetch the seller.
+    #[clap(
+        long = "kleinanzeigen-search-limit",
+        env = "KLEINANZEIGEN_SEARCH_LIMIT",
+        id = "kleinanzeigen_search_limit",
+        default_value = "5",
+        hide_env_values = true
+    )]
+    pub search_limit: u32,
+
+    /// Heartbeat URL for the Kleinanzeigen connection.
+    #[clap(
+        long = "kleinanzeigen-heartbeat-url",
+        env = "KLEINANZEIGEN_HEARTBEAT_URL",
+        id = "kleinanzeigen_heartbeat_url",
+        hide_env_values = true
+    )]
+    pub heartbeat_url: Option<Url>,
+}
+
 #[derive(Parser)]
 #[clap(next_help_heading = "Telegram")]
 pub struct TelegramArgs {
@@ -135,6 +460,8 @@ pub struct TelegramArgs {
     pub bot_token: String,
 
     /// Timeout for Telegram long polling, in seconds.
+    ///
+    /// In the webhook mode, this is the heartbeat interval.
     #[clap(
         long = "telegram-poll-timeout-secs",
         env = "TELEGRAM_POLL_TIMEOUT_SECS",
@@ -153,6 +480,15 @@ pub struct TelegramArgs {
     )]
     pub authorized_chat_ids: Vec<i64>,
 
+    /// Admin chat ID, which is authorized and may also invite others.
+    #[clap(
+        long = "telegram-admin-chat-id",
+        env = "TELEGRAM_ADMIN_CHAT_IDS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    pub admin_chat_ids: Vec<i64>,
+
     /// Heartbeat URL for the Telegram bot.
     #[clap(
         long = "telegram-heartbeat-url",
@@ -161,4 +497,61 @@ pub struct TelegramArgs {
         hide_env_values = true
     )]
     pub heartbeat_url: Option<Url>,
+
+    /// Public webhook URL: if set, the bot receives updates via the webhook instead of long polling.
+    #[clap(long = "telegram-webhook-url", env = "TELEGRAM_WEBHOOK_URL", hide_env_values = true)]
+    pub webhook_url: Option<Url>,
+
+    /// Socket address for the webhook server to listen on.
+    #[clap(
+        long = "telegram-webhook-bind-address",
+        env = "TELEGRAM_WEBHOOK_BIND_ADDRESS",
+        default_value = "0.0.0.0:8080",
+        hide_env_values = true
+    )]
+    pub webhook_bind_address: SocketAddr,
+
+    /// Secret token to validate the webhook requests, random by default.
+    #[clap(
+        long = "telegram-webhook-secret-token",
+        env = "TELEGRAM_WEBHOOK_SECRET_TOKEN",
+        hide_env_values = true
+    )]
+    pub webhook_secret_token: Option<SecretString>,
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn verify_args_ok() {
+        Args::command().debug_assert();
+    }
+
+    fn run_args(args: &[&str]) -> Result<RunArgs> {
+        let args = ["mrktpltsbot", "run", "--telegram-bot-token=test"].iter().chain(args);
+        match Args::try_parse_from(args)?.command {
+            Command::Run(args) => Ok(*args),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn zero_retention_days_fails() {
+        assert!(run_args(&["--retention-days=0"]).is_err());
+        assert!(
+            Args::try_parse_from(["mrktpltsbot", "db", "prune", "--retention-days=0"]).is_err()
+        );
+    }
+
+    #[test]
+    fn retention_shorter_than_duplicate_window_fails() -> Result {
+        run_args(&["--retention-days=7", "--duplicate-window-hours=168"])?.validate()?;
+        let args = run_args(&["--retention-days=6", "--duplicate-window-hours=168"])?;
+        assert!(args.validate().is_err());
+        Ok(())
+    }
 }
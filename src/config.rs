@@ -0,0 +1,309 @@
+//! Optional TOML configuration file, which provides the `run` arguments.
+//!
+//! The keys are the long argument names, for example:
+//!
+//! ```toml
+//! search-interval-secs = 30
+//! telegram-authorize-chat-id = [123456789, -1001234567890]
+//! 2dehands-enabled = true
+//! ```
+//!
+//! Command-line arguments and environment variables take precedence over the file.
+
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use bon::Builder;
+use chrono::TimeDelta;
+use clap::{ArgAction, CommandFactory, Parser, parser::ValueSource};
+use itertools::Itertools;
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::watch,
+    time::sleep,
+};
+use toml::{Table, Value};
+
+use crate::{
+    cli::{Args, Command, RunArgs},
+    prelude::*,
+    quotas::Quotas,
+};
+
+/// Settings, which are applied without restarting the bots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reloadable {
+    pub search_interval: Duration,
+    pub duplicate_window: TimeDelta,
+    pub quotas: Quotas,
+    pub authorized_chat_ids: HashSet<i64>,
+    pub admin_chat_ids: HashSet<i64>,
+}
+
+impl Reloadable {
+    /// Configuration keys of the reloadable settings.
+    const KEYS: [&str; 6] = [
+        "search-interval-secs",
+        "duplicate-window-hours",
+        "max-subscriptions-per-chat",
+        "max-notifications-per-hour",
+        "telegram-authorize-chat-id",
+        "telegram-admin-chat-id",
+    ];
+
+    /// Check whether the chat is authorized by the configuration.
+    pub fn is_authorized(&self, chat_id: i64) -> bool {
+        self.authorized_chat_ids.contains(&chat_id) || self.admin_chat_ids.contains(&chat_id)
+    }
+}
+
+impl From<&RunArgs> for Reloadable {
+    fn from(args: &RunArgs) -> Self {
+        Self {
+            search_interval: Duration::from_secs(args.search_interval_secs),
+            duplicate_window: TimeDelta::hours(args.duplicate_window_hours),
+            quotas: Quotas::from(&args.quotas),
+            authorized_chat_ids: args.telegram.authorized_chat_ids.iter().copied().collect(),
+            admin_chat_ids: args.telegram.admin_chat_ids.iter().copied().collect(),
+        }
+    }
+}
+
+/// Read the configuration file.
+pub fn read(path: &Path) -> Result<Table> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+    text.parse().with_context(|| format!("failed to parse `{}`", path.display()))
+}
+
+/// Parse the command-line arguments, applying the configuration file, if any.
+///
+/// Exits on invalid arguments, like [`clap::Parser::parse`] does.
+pub fn parse_args(raw_args: &[OsString]) -> Result<Args> {
+    let matches = Args::command().ignore_errors(true).try_get_matches_from(raw_args);
+    let path = matches.ok().and_then(|matches| {
+        matches.subcommand_matches("run")?.get_one::<PathBuf>("config").cloned()
+    });
+    let raw_args = match path {
+        Some(path) => merge(raw_args, &read(&path)?)?,
+        None => raw_args.to_vec(),
+    };
+    Ok(Args::parse_from(raw_args))
+}
+
+/// Parse the `run` arguments, applying the configuration.
+pub fn parse_run_args(raw_args: &[OsString], config: &Table) -> Result<RunArgs> {
+    match Args::try_parse_from(merge(raw_args, config)?)?.command {
+        Command::Run(args) => {
+            args.validate()?;
+            Ok(*args)
+        }
+        _ => bail!("the configuration file is only supported by `run`"),
+    }
+}
+
+/// Append the configuration to the command-line arguments.
+///
+/// Only the arguments, which are neither passed on the command line nor set via environment, are appended.
+fn merge(raw_args: &[OsString], config: &Table) -> Result<Vec<OsString>> {
+    let command = Args::command().ignore_errors(true);
+    let matches = command.clone().try_get_matches_from(raw_args)?;
+    let Some(("run", run_matches)) = matches.subcommand() else {
+        bail!("the configuration file is only supported by `run`");
+    };
+    let run_command = command.find_subcommand("run").context("missing `run` subcommand")?;
+
+    let mut raw_args = raw_args.to_vec();
+    for (key, value) in config {
+        let arg = run_command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key.as_str()))
+            .with_context(|| format!("unknown setting `{key}`"))?;
+        if matches!(
+            run_matches.value_source(arg.get_id().as_str()),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable),
+        ) {
+            continue;
+        }
+        if matches!(arg.get_action(), ArgAction::SetTrue) {
+            let value = value.as_bool().with_context(|| format!("`{key}` must be a boolean"))?;
+            if value {
+                raw_args.push(format!("--{key}").into());
+            }
+            continue;
+        }
+        let values = match value {
+            Value::Array(values) => values.iter().map(scalar).collect::<Result<Vec<_>>>(),
+            value => scalar(value).map(|value| vec![value]),
+        }
+        .with_context(|| format!("invalid `{key}`"))?;
+        raw_args.extend(values.into_iter().map(|value| format!("--{key}={value}").into()));
+    }
+    Ok(raw_args)
+}
+
+fn scalar(value: &Value) -> Result<String> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        Value::Integer(value) => Ok(value.to_string()),
+        Value::Float(value) => Ok(value.to_string()),
+        Value::Boolean(value) => Ok(value.to_string()),
+        Value::Datetime(_) | Value::Array(_) | Value::Table(_) => {
+            bail!("expected a string, number, or boolean")
+        }
+    }
+}
+
+/// Reloads the configuration file on `SIGHUP` or when the file changes.
+///
+/// Invalid configuration is reported, and the current settings are kept.
+#[derive(Builder)]
+pub struct Watcher {
+    path: PathBuf,
+
+    /// Original command-line arguments, which take precedence over the file.
+    raw_args: Vec<OsString>,
+
+    /// Currently loaded configuration.
+    config: Table,
+
+    reloadable: watch::Sender<Reloadable>,
+
+    /// Interval between the file modification checks.
+    #[builder(default = Duration::from_secs(5))]
+    poll_interval: Duration,
+}
+
+impl Watcher {
+    pub async fn run(mut self) {
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => Some(hangups),
+            Err(error) => {
+                warn!("⚠️ Could not listen to `SIGHUP`: {error:#}");
+                None
+            }
+        };
+        info!(path = %self.path.display(), "🔄 Watching the configuration…");
+        let mut modified_at = self.modified_at();
+        loop {
+            tokio::select! {
+                Some(()) = async { hangups.as_mut()?.recv().await } => {
+                    info!("📥 Received `SIGHUP`");
+                }
+                () = sleep(self.poll_interval) => {
+                    let new_modified_at = self.modified_at();
+                    if new_modified_at == modified_at {
+                        continue;
+                    }
+                    modified_at = new_modified_at;
+                }
+            }
+            if let Err(error) = self.reload() {
+                error!("‼️ Failed to reload the configuration, keeping the current one: {error:#}");
+            }
+        }
+    }
+
+    fn modified_at(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    fn reload(&mut self) -> Result {
+        let config = read(&self.path)?;
+        let reloadable = Reloadable::from(&parse_run_args(&self.raw_args, &config)?);
+        for key in self.config.keys().chain(config.keys()).unique() {
+            if !Reloadable::KEYS.contains(&key.as_str()) && self.config.get(key) != config.get(key)
+            {
+                warn!(key, "⚠️ The setting requires a restart to take effect");
+            }
+        }
+        self.config = config;
+        if self.reloadable.send_if_modified(|current| {
+            let is_modified = *current != reloadable;
+            *current = reloadable;
+            is_modified
+        }) {
+            info!("✅ Reloaded the configuration");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_args(args: &[&str]) -> Vec<OsString> {
+        ["mrktpltsbot", "run"].into_iter().chain(args.iter().copied()).map(OsString::from).collect()
+    }
+
+    #[test]
+    fn parse_run_args_ok() -> Result {
+        let config: Table = "
+            telegram-bot-token = 'test'
+            search-interval-secs = 30
+            telegram-authorize-chat-id = [1, -100]
+            2dehands-enabled = true
+            kleinanzeigen-enabled = false
+        "
+        .parse()?;
+        let args = parse_run_args(&raw_args(&[]), &config)?;
+        assert_eq!(args.search_interval_secs, 30);
+        assert_eq!(args.telegram.authorized_chat_ids, [1, -100]);
+        assert!(args.marketplaces.tweedehands.enabled);
+        assert!(!args.marketplaces.kleinanzeigen.enabled);
+        Ok(())
+    }
+
+    #[test]
+    fn command_line_overrides_config_ok() -> Result {
+        let config: Table = "telegram-bot-token = 'test'\nsearch-interval-secs = 30".parse()?;
+        let args = parse_run_args(&raw_args(&["--search-interval-secs=90"]), &config)?;
+        assert_eq!(args.search_interval_secs, 90);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_setting_fails() -> Result {
+        let config: Table = "telegram-bot-token = 'test'\nsearch-interval = 30".parse()?;
+        assert!(parse_run_args(&raw_args(&[]), &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_value_fails() -> Result {
+        let config: Table = "telegram-bot-token = 'test'\nsearch-interval-secs = 'soon'".parse()?;
+        assert!(parse_run_args(&raw_args(&[]), &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn reload_keeps_current_on_error() -> Result {
+        let path = std::env::temp_dir().join(format!("mrktpltsbot-{}.toml", std::process::id()));
+        std::fs::write(&path, "telegram-bot-token = 'test'\nsearch-interval-secs = 30")?;
+        let config = read(&path)?;
+        let (sender, receiver) =
+            watch::channel(Reloadable::from(&parse_run_args(&raw_args(&[]), &config)?));
+        let mut watcher = Watcher::builder()
+            .path(path.clone())
+            .raw_args(raw_args(&[]))
+            .config(config)
+            .reloadable(sender)
+            .build();
+
+        std::fs::write(&path, "telegram-bot-token = 'test'\nsearch-interval-secs = 45")?;
+        watcher.reload()?;
+        assert_eq!(receiver.borrow().search_interval, Duration::from_secs(45));
+
+        std::fs::write(&path, "telegram-bot-token = 'test'\nsearch-interval-secs = -1")?;
+        assert!(watcher.reload().is_err());
+        assert_eq!(receiver.borrow().search_interval, Duration::from_secs(45));
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
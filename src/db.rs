@@ -1,55 +1,133 @@
+mod authorized_chat;
+mod blocked_seller;
+mod chat;
+mod dump;
+mod invite;
 mod item;
+mod item_details;
+mod item_fingerprint;
 mod key_values;
 mod notification;
+mod outbox;
 mod search_query;
 mod subscription;
+mod vinted_lookup;
 
-use std::{path::Path, sync::Arc};
+use std::{path::Path, time::Duration};
 
 use anyhow::Context;
 use sqlx::{
-    ConnectOptions,
     FromRow,
+    Sqlite,
     SqliteConnection,
+    SqlitePool,
+    Transaction,
     migrate::Migrator,
-    sqlite::SqliteConnectOptions,
+    pool::PoolConnection,
+    sqlite::{
+        SqliteAutoVacuum,
+        SqliteConnectOptions,
+        SqliteJournalMode,
+        SqlitePoolOptions,
+        SqliteSynchronous,
+    },
 };
 use sqlx_sqlite::SqliteRow;
-use tokio::sync::{Mutex, MutexGuard};
 
 pub use self::{
-    item::{Item, Items},
+    authorized_chat::{AuthorizedChat, AuthorizedChats},
+    blocked_seller::{BlockedSeller, BlockedSellers},
+    chat::{Chat, Chats},
+    dump::Dump,
+    invite::{Invite, Invites},
+    item::{Item, Items, NotifiedItem},
+    item_details::ItemDetails,
+    item_fingerprint::ItemFingerprints,
     key_values::{KeyValues, KeyedMessage},
     notification::{Notification, Notifications},
+    outbox::{Outbox, OutboxEntry},
     search_query::{SearchQueries, SearchQuery},
     subscription::{Subscription, Subscriptions},
+    vinted_lookup::VintedLookups,
 };
 use crate::prelude::*;
 
 static MIGRATOR: Migrator = sqlx::migrate!();
 
+/// How long a connection waits for another one to release the database lock.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Database connection pool.
+///
+/// Acquire a connection for as short as possible, and never hold it across network calls.
 #[must_use]
 #[derive(Clone)]
-pub struct Db(Arc<Mutex<SqliteConnection>>);
+pub struct Db(SqlitePool);
 
 impl Db {
     /// TODO: change `Path` into `AsRef<Path>`.
     #[instrument(skip_all)]
     pub async fn try_new(path: &Path) -> Result<Self> {
-        let mut connection = SqliteConnectOptions::new()
+        let connect_options = SqliteConnectOptions::new()
             .create_if_missing(true)
             .filename(path)
-            .connect()
+            .auto_vacuum(SqliteAutoVacuum::Incremental)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT);
+        let pool_options = if path == Path::new(":memory:") {
+            // Every connection would open its own in-memory database, so keep the only one alive.
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            SqlitePoolOptions::new()
+        };
+        let pool = pool_options
+            .connect_with(connect_options)
             .await
             .with_context(|| format!("failed to open database `{}`", path.display()))?;
-        MIGRATOR.run(&mut connection).await.context("failed to migrate the database")?;
+        MIGRATOR.run(&pool).await.context("failed to migrate the database")?;
+        let this = Self(pool);
+        enable_incremental_vacuum(&mut *this.connection().await?).await?;
         info!(path = %path.display(), canonical = %path.canonicalize()?.display(), "✅ The database is ready");
-        Ok(Self(Arc::new(Mutex::new(connection))))
+        Ok(this)
+    }
+
+    /// Acquire a connection from the pool.
+    pub async fn connection(&self) -> Result<PoolConnection<Sqlite>> {
+        self.0.acquire().await.context("failed to acquire a database connection")
+    }
+
+    /// Begin a transaction, which is rolled back unless committed.
+    pub async fn begin(&self) -> Result<Transaction<'static, Sqlite>> {
+        self.0.begin().await.context("failed to begin a transaction")
+    }
+
+    /// Release the free pages back to the file system.
+    #[instrument(skip_all)]
+    pub async fn incremental_vacuum(&self) -> Result {
+        sqlx::query("PRAGMA incremental_vacuum")
+            .execute(&self.0)
+            .await
+            .context("failed to run the incremental vacuum")?;
+        Ok(())
     }
 
-    /// Lock and return the connection.
-    pub async fn connection(&self) -> MutexGuard<SqliteConnection> {
-        self.0.lock().await
+    /// Write a consistent copy of the live database into the new file.
+    #[instrument(skip_all, fields(path = %path.display()))]
+    pub async fn backup(&self, path: &Path) -> Result {
+        ensure!(!path.exists(), "`{}` already exists", path.display());
+        let path_str =
+            path.to_str().with_context(|| format!("invalid path: `{}`", path.display()))?;
+        sqlx::query("VACUUM INTO ?1")
+            .bind(path_str)
+            .execute(&self.0)
+            .await
+            .with_context(|| format!("failed to back up the database into `{}`", path.display()))?;
+        Ok(())
     }
 
     pub async fn subscriptions_of(&self, chat_id: i64) -> Result<Vec<(Subscription, SearchQuery)>> {
@@ -63,7 +141,7 @@ impl Db {
 
         sqlx::query(QUERY)
             .bind(chat_id)
-            .fetch_all(&mut *self.connection().await)
+            .fetch_all(&self.0)
             .await
             .with_context(|| format!("failed to fetch subscriptions of chat #{chat_id}"))?
             .into_iter()
@@ -71,19 +149,41 @@ impl Db {
             .collect()
     }
 
+    /// Retrieve the subscriptions of all the chats.
+    pub async fn all_subscriptions(&self) -> Result<Vec<(Subscription, SearchQuery)>> {
+        // language=sql
+        const QUERY: &str = r"
+            SELECT search_queries.*, subscriptions.* FROM subscriptions
+            JOIN search_queries ON search_queries.hash = subscriptions.query_hash
+            ORDER BY subscriptions.chat_id, search_queries.text
+        ";
+
+        sqlx::query(QUERY)
+            .fetch_all(&self.0)
+            .await
+            .context("failed to fetch all the subscriptions")?
+            .into_iter()
+            .map(enriched_subscription_from_row)
+            .collect()
+    }
+
     /// Retrieve the first subscription, or `None` – if there are no subscriptions.
+    ///
+    /// Subscriptions of paused chats are skipped.
     #[instrument(skip_all)]
     pub async fn first_subscription(&self) -> Result<Option<(Subscription, SearchQuery)>> {
         // language=sql
         const QUERY: &str = r"
             SELECT search_queries.*, subscriptions.* FROM subscriptions
             JOIN search_queries ON search_queries.hash = subscriptions.query_hash
+            LEFT JOIN chats ON chats.id = subscriptions.chat_id
+            WHERE NOT COALESCE(chats.is_paused, FALSE)
             ORDER BY subscriptions.chat_id, subscriptions.query_hash
             LIMIT 1
         ";
 
         sqlx::query(QUERY)
-            .fetch_optional(&mut *self.connection().await)
+            .fetch_optional(&self.0)
             .await
             .context("failed to fetch the first subscription")?
             .map(enriched_subscription_from_row)
@@ -91,6 +191,8 @@ impl Db {
     }
 
     /// Retrieve the next subscription, or [`None`] – if `current` is the last subscription.
+    ///
+    /// Subscriptions of paused chats are skipped.
     #[instrument(skip_all, fields(query_hash = current.query_hash, chat_id = current.chat_id))]
     pub async fn next_subscription(
         &self,
@@ -100,7 +202,9 @@ impl Db {
         const QUERY: &str = r"
             SELECT search_queries.*, subscriptions.* FROM subscriptions
             JOIN search_queries ON search_queries.hash = subscriptions.query_hash
+            LEFT JOIN chats ON chats.id = subscriptions.chat_id
             WHERE (subscriptions.chat_id, subscriptions.query_hash) > (?1, ?2)
+              AND NOT COALESCE(chats.is_paused, FALSE)
             ORDER BY subscriptions.chat_id, subscriptions.query_hash
             LIMIT 1
         ";
@@ -108,7 +212,7 @@ impl Db {
         sqlx::query(QUERY)
             .bind(current.chat_id)
             .bind(current.query_hash)
-            .fetch_optional(&mut *self.connection().await)
+            .fetch_optional(&self.0)
             .await
             .context("failed to fetch the next subscription")?
             .map(enriched_subscription_from_row)
@@ -116,6 +220,24 @@ impl Db {
     }
 }
 
+/// Switch an existing database to the incremental vacuum.
+///
+/// Auto-vacuum mode of an existing database only changes with a full vacuum:
+/// <https://www.sqlite.org/pragma.html#pragma_auto_vacuum>.
+async fn enable_incremental_vacuum(connection: &mut SqliteConnection) -> Result {
+    const INCREMENTAL: i32 = 2;
+
+    let auto_vacuum: i32 = sqlx::query_scalar("PRAGMA auto_vacuum")
+        .fetch_one(&mut *connection)
+        .await
+        .context("failed to query the auto-vacuum mode")?;
+    if auto_vacuum != INCREMENTAL {
+        info!("🧹 Enabling the incremental vacuum, this may take a while…");
+        sqlx::query("VACUUM").execute(&mut *connection).await.context("failed to vacuum")?;
+    }
+    Ok(())
+}
+
 #[expect(clippy::needless_pass_by_value)]
 fn enriched_subscription_from_row(row: SqliteRow) -> Result<(Subscription, SearchQuery)> {
     Ok((Subscription::from_row(&row)?, SearchQuery::from_row(&row)?))
@@ -141,7 +263,7 @@ mod tests {
 
         // Setting up:
         {
-            let connection = &mut *db.connection().await;
+            let connection = &mut *db.connection().await?;
             SearchQueries(connection).upsert(&search_query_1).await?;
             SearchQueries(connection).upsert(&search_query_2).await?;
             Subscriptions(connection).upsert(subscription_first).await?;
@@ -165,9 +287,33 @@ mod tests {
         // Test filtering by chat:
         assert_eq!(
             db.subscriptions_of(subscription_first.chat_id).await?,
-            &[expected_entry_first, expected_entry_middle]
+            &[expected_entry_first.clone(), expected_entry_middle.clone()]
         );
 
+        // Test all the chats:
+        assert_eq!(
+            db.all_subscriptions().await?,
+            &[expected_entry_first, expected_entry_middle, (subscription_last, search_query_2)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_paused_chat_skipped_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let search_query = SearchQuery::from("unifi");
+        let subscription_paused = Subscription { chat_id: 42, query_hash: search_query.hash };
+        let subscription_active = Subscription { chat_id: 43, query_hash: search_query.hash };
+        {
+            let connection = &mut *db.connection().await?;
+            SearchQueries(connection).upsert(&search_query).await?;
+            Subscriptions(connection).upsert(subscription_paused).await?;
+            Subscriptions(connection).upsert(subscription_active).await?;
+            Chats(connection).upsert(&Chat { is_paused: true, ..Chat::new(42) }).await?;
+        }
+        assert_eq!(db.first_subscription().await?.unwrap().0, subscription_active);
+        assert!(db.next_subscription(&subscription_active).await?.is_none());
         Ok(())
     }
 
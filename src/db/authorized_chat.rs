@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqliteConnection};
+
+use crate::prelude::*;
+
+/// Chat authorized by redeeming an invite.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct AuthorizedChat {
+    pub chat_id: i64,
+    pub authorized_at: DateTime<Utc>,
+}
+
+pub struct AuthorizedChats<'a>(pub &'a mut SqliteConnection);
+
+impl AuthorizedChats<'_> {
+    #[instrument(skip_all, fields(chat_id = chat.chat_id))]
+    pub async fn upsert(&mut self, chat: &AuthorizedChat) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            INSERT INTO authorized_chats (chat_id, authorized_at) VALUES (?1, ?2)
+            ON CONFLICT DO NOTHING
+        ";
+        sqlx::query(QUERY)
+            .bind(chat.chat_id)
+            .bind(chat.authorized_at)
+            .execute(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to authorize chat #{}", chat.chat_id))?;
+
+        Ok(())
+    }
+
+    #[instrument(skip_all, fields(chat_id = chat_id))]
+    pub async fn exists(&mut self, chat_id: i64) -> Result<bool> {
+        // language=sql
+        const QUERY: &str = "SELECT EXISTS(SELECT 1 FROM authorized_chats WHERE chat_id = ?1)";
+        sqlx::query_scalar(QUERY)
+            .bind(chat_id)
+            .fetch_one(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to check whether chat #{chat_id} is authorized"))
+    }
+
+    #[instrument(skip_all)]
+    pub async fn fetch_all(&mut self) -> Result<Vec<AuthorizedChat>> {
+        // language=sql
+        const QUERY: &str = "SELECT * FROM authorized_chats ORDER BY authorized_at";
+        sqlx::query_as(QUERY)
+            .fetch_all(&mut *self.0)
+            .await
+            .context("failed to fetch the authorized chats")
+    }
+
+    /// Revoke the authorization.
+    ///
+    /// # Returns
+    ///
+    /// Whether the chat was authorized.
+    #[instrument(skip_all, fields(chat_id = chat_id))]
+    pub async fn delete(&mut self, chat_id: i64) -> Result<bool> {
+        // language=sql
+        const QUERY: &str = "DELETE FROM authorized_chats WHERE chat_id = ?1";
+        let result =
+            sqlx::query(QUERY).bind(chat_id).execute(&mut *self.0).await.with_context(|| {
+                format!("failed to revoke the authorization of chat #{chat_id}")
+            })?;
+        Ok(result.rows_affected() != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::db::Db;
+
+    #[tokio::test]
+    async fn crud_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        let mut authorized_chats = AuthorizedChats(&mut connection);
+
+        assert!(!authorized_chats.exists(42).await?);
+
+        let chat = AuthorizedChat { chat_id: 42, authorized_at: Utc::now() };
+        authorized_chats.upsert(&chat).await?;
+        authorized_chats.upsert(&chat).await?; // verify conflicts
+        assert!(authorized_chats.exists(42).await?);
+        assert_eq!(authorized_chats.fetch_all().await?, [chat]);
+
+        assert!(authorized_chats.delete(42).await?);
+        assert!(!authorized_chats.delete(42).await?);
+        assert!(!authorized_chats.exists(42).await?);
+
+        Ok(())
+    }
+}
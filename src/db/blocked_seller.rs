@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqliteConnection};
+
+use crate::{marketplace::item::Item, prelude::*};
+
+/// Seller blocked in a chat.
+#[derive(Clone, Debug, Eq, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct BlockedSeller {
+    pub chat_id: i64,
+    pub marketplace_id: String,
+
+    /// See [`crate::marketplace::item::Seller::key`].
+    pub seller_id: String,
+
+    pub blocked_at: DateTime<Utc>,
+}
+
+impl BlockedSeller {
+    pub fn blocks(&self, item: &Item) -> bool {
+        self.marketplace_id == item.marketplace_id
+            && item.seller.as_ref().is_some_and(|seller| seller.key() == self.seller_id)
+    }
+}
+
+pub struct BlockedSellers<'a>(pub &'a mut SqliteConnection);
+
+impl BlockedSellers<'_> {
+    #[instrument(skip_all, fields(chat_id = seller.chat_id, marketplace_id = seller.marketplace_id, seller_id = seller.seller_id))]
+    pub async fn upsert(&mut self, seller: &BlockedSeller) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            INSERT INTO blocked_sellers (chat_id, marketplace_id, seller_id, blocked_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT DO NOTHING
+        ";
+        sqlx::query(QUERY)
+            .bind(seller.chat_id)
+            .bind(&seller.marketplace_id)
+            .bind(&seller.seller_id)
+            .bind(seller.blocked_at)
+            .execute(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to block seller `{}`", seller.seller_id))?;
+
+        Ok(())
+    }
+
+    /// Unblock the seller.
+    ///
+    /// # Returns
+    ///
+    /// Whether the seller was blocked.
+    #[instrument(skip_all, fields(chat_id = chat_id, marketplace_id = marketplace_id, seller_id = seller_id))]
+    pub async fn delete(
+        &mut self,
+        chat_id: i64,
+        marketplace_id: &str,
+        seller_id: &str,
+    ) -> Result<bool> {
+        // language=sql
+        const QUERY: &str = "
+            DELETE FROM blocked_sellers
+            WHERE chat_id = ?1 AND marketplace_id = ?2 AND seller_id = ?3
+        ";
+        let result = sqlx::query(QUERY)
+            .bind(chat_id)
+            .bind(marketplace_id)
+            .bind(seller_id)
+            .execute(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to unblock seller `{seller_id}`"))?;
+        Ok(result.rows_affected() != 0)
+    }
+
+    #[instrument(skip_all, fields(chat_id = chat_id))]
+    pub async fn fetch_all_of(&mut self, chat_id: i64) -> Result<Vec<BlockedSeller>> {
+        // language=sql
+        const QUERY: &str = "SELECT * FROM blocked_sellers WHERE chat_id = ?1 ORDER BY blocked_at";
+        sqlx::query_as(QUERY)
+            .bind(chat_id)
+            .fetch_all(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to fetch the blocked sellers of chat #{chat_id}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::db::Db;
+
+    #[tokio::test]
+    async fn crud_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        let mut blocked_sellers = BlockedSellers(&mut connection);
+
+        let seller = BlockedSeller {
+            chat_id: 42,
+            marketplace_id: "marktplaats".to_string(),
+            seller_id: "23640587".to_string(),
+            blocked_at: Utc::now(),
+        };
+        blocked_sellers.upsert(&seller).await?;
+        blocked_sellers.upsert(&seller).await?; // verify conflicts
+        assert_eq!(blocked_sellers.fetch_all_of(42).await?, [seller]);
+        assert!(blocked_sellers.fetch_all_of(43).await?.is_empty());
+
+        assert!(blocked_sellers.delete(42, "marktplaats", "23640587").await?);
+        assert!(!blocked_sellers.delete(42, "marktplaats", "23640587").await?);
+        assert!(blocked_sellers.fetch_all_of(42).await?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqliteConnection};
+
+use crate::prelude::*;
+
+/// Per-chat settings.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct Chat {
+    pub id: i64,
+
+    /// Whether the subscription notifications are paused.
+    pub is_paused: bool,
+
+    /// Whether to send all the item photos as an album, or only the main one.
+    pub sends_album: bool,
+}
+
+impl Chat {
+    /// Default settings for a chat that has never changed them.
+    pub const fn new(id: i64) -> Self {
+        Self { id, is_paused: false, sends_album: false }
+    }
+}
+
+pub struct Chats<'a>(pub &'a mut SqliteConnection);
+
+impl Chats<'_> {
+    /// Fetch the chat settings, or the defaults – if the chat has not been stored yet.
+    #[instrument(skip_all, fields(chat_id = chat_id))]
+    pub async fn fetch(&mut self, chat_id: i64) -> Result<Chat> {
+        // language=sql
+        const QUERY: &str = "SELECT * FROM chats WHERE id = ?1";
+
+        let chat = sqlx::query_as(QUERY)
+            .bind(chat_id)
+            .fetch_optional(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to fetch chat #{chat_id}"))?;
+        Ok(chat.unwrap_or_else(|| Chat::new(chat_id)))
+    }
+
+    #[instrument(skip_all, fields(id = chat.id))]
+    pub async fn upsert(&mut self, chat: &Chat) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            INSERT INTO chats (id, is_paused, sends_album) VALUES (?1, ?2, ?3)
+            ON CONFLICT DO UPDATE SET is_paused = ?2, sends_album = ?3
+        ";
+        sqlx::query(QUERY)
+            .bind(chat.id)
+            .bind(chat.is_paused)
+            .bind(chat.sends_album)
+            .execute(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to upsert chat #{}", chat.id))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::db::Db;
+
+    #[tokio::test]
+    async fn fetch_and_upsert_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        let mut chats = Chats(&mut connection);
+
+        assert_eq!(chats.fetch(42).await?, Chat::new(42));
+
+        let chat = Chat { id: 42, is_paused: true, sends_album: true };
+        chats.upsert(&chat).await?;
+        chats.upsert(&chat).await?; // verify conflicts
+        assert_eq!(chats.fetch(42).await?, chat);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! Portable JSON dump of the user data.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqliteConnection};
+
+use crate::{
+    db::{
+        AuthorizedChat,
+        AuthorizedChats,
+        BlockedSeller,
+        BlockedSellers,
+        Chat,
+        Chats,
+        Item,
+        Items,
+        SearchQueries,
+        SearchQuery,
+        Subscription,
+        Subscriptions,
+    },
+    prelude::*,
+};
+
+/// Current dump format version, bump it on incompatible changes.
+const VERSION: u32 = 1;
+
+/// Search queries, subscriptions, chat settings and notification history.
+///
+/// Caches and the outbox are not included, the bot rebuilds them as it goes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Dump {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub search_queries: Vec<SearchQuery>,
+    pub subscriptions: Vec<Subscription>,
+    pub chats: Vec<Chat>,
+    pub authorized_chats: Vec<AuthorizedChat>,
+    pub blocked_sellers: Vec<BlockedSeller>,
+
+    /// Snapshots of the notified items.
+    pub items: Vec<Item>,
+
+    pub notifications: Vec<NotificationRecord>,
+}
+
+/// Notification with its timestamp.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct NotificationRecord {
+    pub item_id: String,
+    pub chat_id: i64,
+    pub notified_at: Option<DateTime<Utc>>,
+
+    /// See [`crate::db::Notifications::upsert_grouped`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grouped_into: Option<String>,
+}
+
+impl Dump {
+    /// Export the data, preferably within a transaction to get a consistent view.
+    #[instrument(skip_all)]
+    pub async fn export(connection: &mut SqliteConnection) -> Result<Self> {
+        Ok(Self {
+            version: VERSION,
+            exported_at: Utc::now(),
+            search_queries: fetch_all(connection, "SELECT * FROM search_queries ORDER BY hash")
+                .await?,
+            subscriptions: fetch_all(
+                connection,
+                "SELECT * FROM subscriptions ORDER BY chat_id, query_hash",
+            )
+            .await?,
+            chats: fetch_all(connection, "SELECT * FROM chats ORDER BY id").await?,
+            authorized_chats: AuthorizedChats(connection).fetch_all().await?,
+            blocked_sellers: fetch_all(
+                connection,
+                "SELECT * FROM blocked_sellers ORDER BY chat_id, marketplace_id, seller_id",
+            )
+            .await?,
+            // Items seen before the snapshots were introduced have no data to export.
+            items: fetch_all(
+                connection,
+                "
+                SELECT * FROM items
+                WHERE title IS NOT NULL AND id IN (SELECT item_id FROM notifications)
+                ORDER BY id
+                ",
+            )
+            .await?,
+            notifications: fetch_all(
+                connection,
+                "SELECT * FROM notifications ORDER BY chat_id, notified_at, item_id",
+            )
+            .await?,
+        })
+    }
+
+    /// Merge the dump into the database, preferably within a transaction.
+    #[instrument(skip_all)]
+    pub async fn import(&self, connection: &mut SqliteConnection) -> Result {
+        ensure!(self.version == VERSION, "unsupported dump version: {}", self.version);
+
+        for query in &self.search_queries {
+            SearchQueries(connection).upsert(query).await?;
+        }
+        for subscription in &self.subscriptions {
+            Subscriptions(connection).upsert(*subscription).await?;
+        }
+        for chat in &self.chats {
+            Chats(connection).upsert(chat).await?;
+        }
+        for authorized_chat in &self.authorized_chats {
+            AuthorizedChats(connection).upsert(authorized_chat).await?;
+        }
+        for blocked_seller in &self.blocked_sellers {
+            BlockedSellers(connection).upsert(blocked_seller).await?;
+        }
+        for item in &self.items {
+            Items(connection).upsert(item).await?;
+        }
+        for notification in &self.notifications {
+            import_notification(connection, notification).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn fetch_all<T>(connection: &mut SqliteConnection, query: &'static str) -> Result<Vec<T>>
+where
+    T: for<'r> FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+{
+    sqlx::query_as(query)
+        .fetch_all(&mut *connection)
+        .await
+        .with_context(|| format!("failed to export: `{}`", query.trim()))
+}
+
+/// Insert the notification, keeping its timestamp.
+///
+/// The item may lack its snapshot, so insert the bare item in that case.
+async fn import_notification(
+    connection: &mut SqliteConnection,
+    notification: &NotificationRecord,
+) -> Result {
+    // language=sql
+    const INSERT_ITEM_QUERY: &str = "
+        INSERT INTO items (id, updated_at) VALUES (?1, ?2)
+        ON CONFLICT DO NOTHING
+    ";
+
+    // language=sql
+    const INSERT_NOTIFICATION_QUERY: &str = "
+        INSERT INTO notifications (item_id, chat_id, notified_at, grouped_into) VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT DO NOTHING
+    ";
+
+    sqlx::query(INSERT_ITEM_QUERY)
+        .bind(&notification.item_id)
+        .bind(notification.notified_at.unwrap_or_else(Utc::now))
+        .execute(&mut *connection)
+        .await
+        .with_context(|| format!("failed to import the item `{}`", notification.item_id))?;
+    sqlx::query(INSERT_NOTIFICATION_QUERY)
+        .bind(&notification.item_id)
+        .bind(notification.chat_id)
+        .bind(notification.notified_at)
+        .bind(&notification.grouped_into)
+        .execute(&mut *connection)
+        .await
+        .with_context(|| {
+            format!(
+                "failed to import the notification of item `{}` in chat #{}",
+                notification.item_id, notification.chat_id,
+            )
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::db::{Db, Notification, Notifications};
+
+    #[tokio::test]
+    async fn export_import_ok() -> Result {
+        let source = Db::try_new(Path::new(":memory:")).await?;
+        {
+            let connection = &mut *source.connection().await?;
+            let search_query = SearchQuery::from("unifi");
+            SearchQueries(connection).upsert(&search_query).await?;
+            Subscriptions(connection)
+                .upsert(Subscription { query_hash: search_query.hash, chat_id: 42 })
+                .await?;
+            Chats(connection).upsert(&Chat { is_paused: true, ..Chat::new(42) }).await?;
+            AuthorizedChats(connection)
+                .upsert(&AuthorizedChat { chat_id: 42, authorized_at: Utc::now() })
+                .await?;
+            BlockedSellers(connection)
+                .upsert(&BlockedSeller {
+                    chat_id: 42,
+                    marketplace_id: "vinted".to_string(),
+                    seller_id: "1234".to_string(),
+                    blocked_at: Utc::now(),
+                })
+                .await?;
+            Items(connection).upsert(&Item::test("m42")).await?;
+            Items(connection).upsert(&Item::test("m43")).await?;
+            Notifications(connection)
+                .upsert(&Notification { item_id: "m42".to_string(), chat_id: 42 })
+                .await?;
+        }
+        let dump = Dump::export(&mut *source.connection().await?).await?;
+        assert_eq!(dump.items.len(), 1, "only the notified items are exported");
+
+        // Round trip through JSON:
+        let dump: Dump = serde_json::from_str(&serde_json::to_string(&dump)?)?;
+
+        let target = Db::try_new(Path::new(":memory:")).await?;
+        dump.import(&mut *target.connection().await?).await?;
+        let imported = Dump::export(&mut *target.connection().await?).await?;
+        assert_eq!(imported.search_queries, dump.search_queries);
+        assert_eq!(imported.subscriptions, dump.subscriptions);
+        assert_eq!(imported.chats, dump.chats);
+        assert_eq!(imported.authorized_chats, dump.authorized_chats);
+        assert_eq!(imported.blocked_sellers, dump.blocked_sellers);
+        assert_eq!(imported.items, dump.items);
+        assert_eq!(imported.notifications.len(), 1);
+
+        // Importing is idempotent:
+        dump.import(&mut *target.connection().await?).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, SqliteConnection};
+
+use crate::prelude::*;
+
+/// Invite which authorizes the chat that redeems it.
+#[derive(Clone, Debug, Eq, PartialEq, FromRow)]
+pub struct Invite {
+    pub token: Vec<u8>,
+    pub expires_at: DateTime<Utc>,
+
+    /// Reusable invites stay valid until they expire, otherwise an invite is deleted once redeemed.
+    pub is_reusable: bool,
+}
+
+impl Invite {
+    /// Generate a new invite with a random token.
+    pub fn generate(expires_at: DateTime<Utc>, is_reusable: bool) -> Self {
+        Self { token: rand::random::<[u8; 16]>().to_vec(), expires_at, is_reusable }
+    }
+}
+
+pub struct Invites<'a>(pub &'a mut SqliteConnection);
+
+impl Invites<'_> {
+    #[instrument(skip_all, fields(expires_at = ?invite.expires_at, is_reusable = invite.is_reusable))]
+    pub async fn insert(&mut self, invite: &Invite) -> Result {
+        // language=sql
+        const QUERY: &str =
+            "INSERT INTO invites (token, expires_at, is_reusable) VALUES (?1, ?2, ?3)";
+        sqlx::query(QUERY)
+            .bind(&invite.token)
+            .bind(invite.expires_at)
+            .bind(invite.is_reusable)
+            .execute(&mut *self.0)
+            .await
+            .context("failed to insert the invite")?;
+
+        Ok(())
+    }
+
+    /// Redeem the invite: delete it if it is expired or one-time.
+    ///
+    /// # Returns
+    ///
+    /// Whether the invite was valid.
+    #[instrument(skip_all)]
+    pub async fn redeem(&mut self, token: &[u8], now: DateTime<Utc>) -> Result<bool> {
+        // language=sql
+        const SELECT_QUERY: &str = "SELECT * FROM invites WHERE token = ?1";
+
+        // language=sql
+        const DELETE_QUERY: &str = "DELETE FROM invites WHERE token = ?1";
+
+        let invite: Option<Invite> = sqlx::query_as(SELECT_QUERY)
+            .bind(token)
+            .fetch_optional(&mut *self.0)
+            .await
+            .context("failed to fetch the invite")?;
+        let Some(invite) = invite else {
+            return Ok(false);
+        };
+        let is_expired = invite.expires_at <= now;
+        if is_expired || !invite.is_reusable {
+            sqlx::query(DELETE_QUERY)
+                .bind(token)
+                .execute(&mut *self.0)
+                .await
+                .context("failed to delete the invite")?;
+        }
+        Ok(!is_expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::db::Db;
+
+    #[tokio::test]
+    async fn redeem_one_time_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        let mut invites = Invites(&mut connection);
+        let now = Utc::now();
+
+        let invite = Invite::generate(now + TimeDelta::days(1), false);
+        invites.insert(&invite).await?;
+        assert!(invites.redeem(&invite.token, now).await?);
+        assert!(!invites.redeem(&invite.token, now).await?, "the invite must be one-time");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn redeem_reusable_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        let mut invites = Invites(&mut connection);
+        let now = Utc::now();
+
+        let invite = Invite::generate(now + TimeDelta::days(1), true);
+        invites.insert(&invite).await?;
+        assert!(invites.redeem(&invite.token, now).await?);
+        assert!(invites.redeem(&invite.token, now).await?);
+        assert!(
+            !invites.redeem(&invite.token, now + TimeDelta::days(2)).await?,
+            "the invite must expire",
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn redeem_unknown_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        assert!(!Invites(&mut connection).redeem(b"unknown", Utc::now()).await?);
+        Ok(())
+    }
+}
@@ -1,31 +1,439 @@
 use chrono::{DateTime, Utc};
-use sqlx::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, FromRow, SqliteConnection};
 
-use crate::prelude::*;
+use crate::{
+    marketplace,
+    marketplace::item::{Amount, Price},
+    prelude::*,
+};
 
-#[derive(Copy, Clone)]
-pub struct Item<'a> {
-    pub id: &'a str,
-    pub updated_at: DateTime<Utc>,
+/// Normalised snapshot of a marketplace item.
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct Item {
+    pub id: String,
+    pub marketplace_id: String,
+    pub url: String,
+    pub title: String,
+    pub description: Option<String>,
+
+    /// See [`marketplace::item::Price::kind`].
+    pub price_kind: String,
+
+    pub price_amount: Option<String>,
+    pub seller_id: Option<String>,
+    pub seller_username: Option<String>,
+    pub seller_url: Option<String>,
+
+    /// Location toponym.
+    pub location: Option<String>,
+
+    /// See [`marketplace::item::Condition::as_str`].
+    pub condition: Option<String>,
+
+    pub picture_url: Option<String>,
+    pub first_seen_at: DateTime<Utc>,
+
+    #[sqlx(rename = "updated_at")]
+    pub last_seen_at: DateTime<Utc>,
+}
+
+impl Item {
+    /// Take the snapshot of the item, which has just been seen.
+    pub fn seen(item: &marketplace::item::Item, seen_at: DateTime<Utc>) -> Self {
+        Self {
+            id: item.id.clone(),
+            marketplace_id: item.marketplace_id.to_string(),
+            url: item.url.to_string(),
+            title: item.title.clone(),
+            description: item.description.clone(),
+            price_kind: item.price.kind().to_string(),
+            price_amount: item.price.asking().map(|amount| amount.0.to_string()),
+            seller_id: item.seller.as_ref().and_then(|seller| seller.id.clone()),
+            seller_username: item.seller.as_ref().map(|seller| seller.username.clone()),
+            seller_url: item.seller.as_ref().map(|seller| seller.profile_url.to_string()),
+            location: item.location.as_ref().map(|location| location.toponym.clone()),
+            condition: item.condition.map(|condition| condition.as_str().to_string()),
+            picture_url: item.picture_urls.first().map(ToString::to_string),
+            first_seen_at: seen_at,
+            last_seen_at: seen_at,
+        }
+    }
+
+    /// Restore the item price from the snapshot.
+    pub fn price(&self) -> Option<Price> {
+        let asking = match &self.price_amount {
+            Some(amount) => Some(Amount(amount.parse().ok()?)),
+            None => None,
+        };
+        Price::from_kind(&self.price_kind, asking)
+    }
+}
+
+/// Item snapshot along with the timestamp of its notification.
+#[derive(Debug, FromRow)]
+pub struct NotifiedItem {
+    #[sqlx(flatten)]
+    pub item: Item,
+
+    /// [`None`] for notifications sent before the timestamps were introduced.
+    pub notified_at: Option<DateTime<Utc>>,
+}
+
+/// Numbers of the pruned rows.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pruned {
+    pub n_items: u64,
+
+    /// Notifications of the pruned items.
+    pub n_notifications: u64,
 }
 
 pub struct Items<'a>(pub &'a mut SqliteConnection);
 
 impl Items<'_> {
-    #[instrument(skip_all, fields(id = item.id, updated_at = ?item.updated_at))]
-    pub async fn upsert(&mut self, item: Item<'_>) -> Result {
+    /// Insert the snapshot, or update it keeping the first sighting timestamp.
+    #[instrument(skip_all, fields(id = item.id, last_seen_at = ?item.last_seen_at))]
+    pub async fn upsert(&mut self, item: &Item) -> Result {
         // language=sql
         const QUERY: &str = "
-            INSERT INTO items (id, updated_at) VALUES (?1, ?2)
-            ON CONFLICT DO UPDATE SET updated_at = ?2
+            INSERT INTO items (
+                id, marketplace_id, url, title, description, price_kind, price_amount,
+                seller_id, seller_username, seller_url, location, condition, picture_url,
+                first_seen_at, updated_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+            ON CONFLICT DO UPDATE SET
+                marketplace_id = ?2,
+                url = ?3,
+                title = ?4,
+                description = COALESCE(?5, description),
+                price_kind = ?6,
+                price_amount = ?7,
+                seller_id = COALESCE(?8, seller_id),
+                seller_username = COALESCE(?9, seller_username),
+                seller_url = COALESCE(?10, seller_url),
+                location = COALESCE(?11, location),
+                condition = COALESCE(?12, condition),
+                picture_url = COALESCE(?13, picture_url),
+                first_seen_at = COALESCE(first_seen_at, ?14),
+                updated_at = ?15
         ";
         sqlx::query(QUERY)
-            .bind(item.id)
-            .bind(item.updated_at)
+            .bind(&item.id)
+            .bind(&item.marketplace_id)
+            .bind(&item.url)
+            .bind(&item.title)
+            .bind(&item.description)
+            .bind(&item.price_kind)
+            .bind(&item.price_amount)
+            .bind(&item.seller_id)
+            .bind(&item.seller_username)
+            .bind(&item.seller_url)
+            .bind(&item.location)
+            .bind(&item.condition)
+            .bind(&item.picture_url)
+            .bind(item.first_seen_at)
+            .bind(item.last_seen_at)
             .execute(&mut *self.0)
             .await
             .with_context(|| format!("failed to upsert the item #{}", item.id))?;
 
         Ok(())
     }
+
+    /// Fetch the item snapshot.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the item is unknown, or was last seen before the snapshots were introduced.
+    #[instrument(skip_all, fields(id = id))]
+    pub async fn fetch(&mut self, id: &str) -> Result<Option<Item>> {
+        // language=sql
+        const QUERY: &str = "SELECT * FROM items WHERE id = ?1 AND title IS NOT NULL";
+
+        sqlx::query_as(QUERY)
+            .bind(id)
+            .fetch_optional(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to fetch the item #{id}"))
+    }
+
+    /// Full-text search the items notified to the chat, newest first.
+    #[instrument(skip_all, fields(chat_id = chat_id, text = text, limit = limit, offset = offset))]
+    pub async fn search_notified(
+        &mut self,
+        chat_id: i64,
+        text: &str,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<NotifiedItem>> {
+        // language=sql
+        const QUERY: &str = "
+            SELECT items.*, notifications.notified_at
+            FROM items_fts
+            JOIN items ON items.id = items_fts.item_id
+            JOIN notifications ON notifications.item_id = items.id AND notifications.chat_id = ?1
+            WHERE items_fts MATCH ?2
+            ORDER BY notifications.notified_at DESC NULLS LAST, items.updated_at DESC
+            LIMIT ?3 OFFSET ?4
+        ";
+
+        let Some(match_query) = match_query(text) else {
+            return Ok(Vec::new());
+        };
+        sqlx::query_as(QUERY)
+            .bind(chat_id)
+            .bind(match_query)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to search the history of chat #{chat_id}"))
+    }
+
+    /// Delete the items, which have not been seen since the timestamp, along with their notifications.
+    ///
+    /// Their details and fingerprints are deleted by the foreign key cascade.
+    /// In the dry run, only count what would be deleted.
+    #[instrument(skip_all, fields(last_seen_before = ?last_seen_before, dry_run = dry_run))]
+    pub async fn prune(
+        &mut self,
+        last_seen_before: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<Pruned> {
+        // language=sql
+        const COUNT_NOTIFICATIONS_QUERY: &str = "
+            SELECT COUNT(*) FROM notifications
+            WHERE item_id IN (SELECT id FROM items WHERE updated_at < ?1)
+        ";
+
+        // language=sql
+        const COUNT_ITEMS_QUERY: &str = "SELECT COUNT(*) FROM items WHERE updated_at < ?1";
+
+        // language=sql
+        const DELETE_NOTIFICATIONS_QUERY: &str = "
+            DELETE FROM notifications
+            WHERE item_id IN (SELECT id FROM items WHERE updated_at < ?1)
+        ";
+
+        // language=sql
+        const DELETE_ITEMS_QUERY: &str = "DELETE FROM items WHERE updated_at < ?1";
+
+        // Delete the notifications explicitly, to count them in the same transaction:
+        let (notifications_query, items_query) = if dry_run {
+            (COUNT_NOTIFICATIONS_QUERY, COUNT_ITEMS_QUERY)
+        } else {
+            (DELETE_NOTIFICATIONS_QUERY, DELETE_ITEMS_QUERY)
+        };
+        let mut transaction = self.0.begin().await?;
+        let n_notifications =
+            count_or_delete(&mut transaction, notifications_query, last_seen_before, dry_run)
+                .await
+                .context("failed to prune the stale notifications")?;
+        let n_items = count_or_delete(&mut transaction, items_query, last_seen_before, dry_run)
+            .await
+            .context("failed to prune the stale items")?;
+        transaction.commit().await.context("failed to commit the pruning")?;
+        Ok(Pruned { n_items, n_notifications })
+    }
+}
+
+/// Run either the counting or the deleting query.
+///
+/// # Returns
+///
+/// Number of the counted or deleted rows.
+async fn count_or_delete(
+    connection: &mut SqliteConnection,
+    query: &'static str,
+    last_seen_before: DateTime<Utc>,
+    dry_run: bool,
+) -> Result<u64> {
+    if dry_run {
+        Ok(sqlx::query_scalar(query).bind(last_seen_before).fetch_one(connection).await?)
+    } else {
+        Ok(sqlx::query(query).bind(last_seen_before).execute(connection).await?.rows_affected())
+    }
+}
+
+/// Build the FTS5 query, which matches all the words by their prefixes.
+///
+/// The words are quoted, so that the user's text cannot break the query syntax.
+///
+/// # Returns
+///
+/// [`None`] if there are no words to search for.
+fn match_query(text: &str) -> Option<String> {
+    let words: Vec<String> =
+        text.split_whitespace().map(|word| format!("\"{}\"*", word.replace('"', "\"\""))).collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[cfg(test)]
+impl Item {
+    /// Minimal snapshot for the tests.
+    pub fn test(id: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            id: id.to_string(),
+            marketplace_id: "marktplaats".to_string(),
+            url: format!("https://www.marktplaats.nl/v/{id}"),
+            title: "Ubiquiti UniFi U6 Pro".to_string(),
+            description: None,
+            price_kind: "fixed".to_string(),
+            price_amount: Some("95".to_string()),
+            seller_id: Some("23640587".to_string()),
+            seller_username: Some("Pavel".to_string()),
+            seller_url: Some("https://www.marktplaats.nl/u/pavel/23640587/".to_string()),
+            location: None,
+            condition: None,
+            picture_url: None,
+            first_seen_at: now,
+            last_seen_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::TimeDelta;
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::db::{Db, Notification, Notifications};
+
+    #[tokio::test]
+    async fn upsert_keeps_first_seen_at_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        let mut items = Items(&mut connection);
+
+        assert_eq!(items.fetch("m42").await?, None);
+
+        let first = Item {
+            description: Some("Nieuw in doos".to_string()),
+            first_seen_at: Utc::now() - TimeDelta::days(1),
+            last_seen_at: Utc::now() - TimeDelta::days(1),
+            ..Item::test("m42")
+        };
+        items.upsert(&first).await?;
+
+        let second = Item { price_amount: Some("80".to_string()), ..Item::test("m42") };
+        items.upsert(&second).await?;
+
+        let item = items.fetch("m42").await?.unwrap();
+        assert_eq!(item.first_seen_at, first.first_seen_at);
+        assert_eq!(item.last_seen_at, second.last_seen_at);
+        assert_eq!(item.price_amount.as_deref(), Some("80"));
+        assert_eq!(item.description, first.description, "the missing details are kept");
+
+        Ok(())
+    }
+
+    #[test]
+    fn price_ok() {
+        assert!(matches!(
+            Item::test("m42").price(),
+            Some(Price::Fixed(Amount(amount))) if amount == Decimal::from(95),
+        ));
+        let item = Item {
+            price_kind: "to_be_agreed".to_string(),
+            price_amount: None,
+            ..Item::test("m42")
+        };
+        assert!(matches!(item.price(), Some(Price::ToBeAgreed)));
+        let item =
+            Item { price_kind: "minimal_bid".to_string(), price_amount: None, ..Item::test("m42") };
+        assert!(item.price().is_none(), "missing the asking amount");
+    }
+
+    #[test]
+    fn match_query_ok() {
+        assert_eq!(match_query("unifi  u6"), Some(r#""unifi"* "u6"*"#.to_string()));
+        assert_eq!(match_query(r#"7" OR"#), Some(r#""7"""* "OR"*"#.to_string()));
+        assert_eq!(match_query(" "), None);
+    }
+
+    #[tokio::test]
+    async fn search_notified_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+
+        let mut items = Items(&mut connection);
+        items
+            .upsert(&Item { title: "Ubiquiti UniFi U6 Pro".to_string(), ..Item::test("m42") })
+            .await?;
+        items.upsert(&Item { title: "UniFi Switch Lite".to_string(), ..Item::test("m43") }).await?;
+        items
+            .upsert(&Item {
+                title: "Access point".to_string(),
+                description: Some("Unifi U6 Lite, nieuw".to_string()),
+                ..Item::test("m44")
+            })
+            .await?;
+        items.upsert(&Item { title: "Unifi U6 Pro".to_string(), ..Item::test("m45") }).await?;
+
+        let mut notifications = Notifications(&mut connection);
+        for item_id in ["m42", "m43", "m44"] {
+            notifications
+                .upsert(&Notification { item_id: item_id.to_string(), chat_id: 42 })
+                .await?;
+        }
+
+        // The title change must be reindexed.
+        Items(&mut connection)
+            .upsert(&Item {
+                title: "Ubiquiti UniFi U6 Pro, als nieuw".to_string(),
+                ..Item::test("m42")
+            })
+            .await?;
+
+        let mut items = Items(&mut connection);
+        let ids = |found: Vec<NotifiedItem>| {
+            found.into_iter().map(|found| found.item.id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(items.search_notified(42, "unif u6", 10, 0).await?), ["m44", "m42"]);
+        assert_eq!(ids(items.search_notified(42, "unif u6", 1, 1).await?), ["m42"]);
+        assert_eq!(ids(items.search_notified(42, "als", 10, 0).await?), ["m42"]);
+        assert_eq!(
+            ids(items.search_notified(42, "pro", 10, 0).await?),
+            ["m42"],
+            "m45 was not notified"
+        );
+        assert!(items.search_notified(43, "unifi", 10, 0).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prune_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+
+        let long_ago = Utc::now() - TimeDelta::days(100);
+        let stale = Item { first_seen_at: long_ago, last_seen_at: long_ago, ..Item::test("m42") };
+        Items(&mut connection).upsert(&stale).await?;
+        Items(&mut connection).upsert(&Item::test("m43")).await?;
+        let mut notifications = Notifications(&mut connection);
+        for (item_id, chat_id) in [("m42", 42), ("m42", 43), ("m43", 42)] {
+            notifications.upsert(&Notification { item_id: item_id.to_string(), chat_id }).await?;
+        }
+
+        let last_seen_before = Utc::now() - TimeDelta::days(30);
+        let expected = Pruned { n_items: 1, n_notifications: 2 };
+        assert_eq!(Items(&mut connection).prune(last_seen_before, true).await?, expected);
+        assert!(Items(&mut connection).fetch("m42").await?.is_some(), "dry run must not delete");
+
+        assert_eq!(Items(&mut connection).prune(last_seen_before, false).await?, expected);
+        assert_eq!(Items(&mut connection).fetch("m42").await?, None);
+        assert!(Items(&mut connection).fetch("m43").await?.is_some());
+        let notification = Notification { item_id: "m42".to_string(), chat_id: 42 };
+        assert!(!Notifications(&mut connection).exists(&notification).await?);
+        assert!(Items(&mut connection).search_notified(42, "unifi", 10, 0).await?.len() == 1);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,72 @@
+use chrono::Utc;
+use sqlx::SqliteConnection;
+
+use crate::{marketplace::item::Details, prelude::*};
+
+/// Cached [`Details`] of the items.
+pub struct ItemDetails<'a>(pub &'a mut SqliteConnection);
+
+impl ItemDetails<'_> {
+    #[instrument(skip_all, fields(item_id = item_id))]
+    pub async fn fetch(&mut self, item_id: &str) -> Result<Option<Details>> {
+        // language=sql
+        const QUERY: &str = "SELECT details FROM item_details WHERE item_id = ?1";
+
+        let details: Option<String> = sqlx::query_scalar(QUERY)
+            .bind(item_id)
+            .fetch_optional(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to fetch the details of item `{item_id}`"))?;
+        details
+            .map(|details| serde_json::from_str(&details))
+            .transpose()
+            .with_context(|| format!("failed to deserialize the details of item `{item_id}`"))
+    }
+
+    #[instrument(skip_all, fields(item_id = item_id))]
+    pub async fn upsert(&mut self, item_id: &str, details: &Details) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            INSERT INTO item_details (item_id, details, fetched_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT DO UPDATE SET details = ?2, fetched_at = ?3
+        ";
+        sqlx::query(QUERY)
+            .bind(item_id)
+            .bind(serde_json::to_string(details)?)
+            .bind(Utc::now())
+            .execute(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to upsert the details of item `{item_id}`"))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::db::{Db, Item, Items};
+
+    #[tokio::test]
+    async fn fetch_and_upsert_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        Items(&mut connection).upsert(&Item::test("vinted::42")).await?;
+        let mut item_details = ItemDetails(&mut connection);
+
+        assert_eq!(item_details.fetch("vinted::42").await?, None);
+
+        let details = Details {
+            description: Some("Unifi u6 pro".to_string()),
+            posted_at: Some(Utc::now()),
+            ..Details::default()
+        };
+        item_details.upsert("vinted::42", &details).await?;
+        item_details.upsert("vinted::42", &details).await?; // verify conflicts
+        assert_eq!(item_details.fetch("vinted::42").await?, Some(details));
+
+        Ok(())
+    }
+}
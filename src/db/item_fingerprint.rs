@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqliteConnection;
+
+use crate::{marketplace::item::Fingerprint, prelude::*};
+
+pub struct ItemFingerprints<'a>(pub &'a mut SqliteConnection);
+
+impl ItemFingerprints<'_> {
+    #[instrument(skip_all, fields(item_id = item_id))]
+    pub async fn fetch(&mut self, item_id: &str) -> Result<Option<Fingerprint>> {
+        // language=sql
+        const QUERY: &str = "SELECT * FROM item_fingerprints WHERE item_id = ?1";
+
+        sqlx::query_as(QUERY)
+            .bind(item_id)
+            .fetch_optional(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to fetch the fingerprint of item `{item_id}`"))
+    }
+
+    #[instrument(skip_all, fields(item_id = item_id))]
+    pub async fn upsert(&mut self, item_id: &str, fingerprint: &Fingerprint) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            INSERT INTO item_fingerprints (item_id, marketplace_id, title, price, seller_id, seller_username, picture_hash)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT DO UPDATE SET
+                marketplace_id = ?2,
+                title = ?3,
+                price = ?4,
+                seller_id = ?5,
+                seller_username = ?6,
+                picture_hash = ?7
+        ";
+        sqlx::query(QUERY)
+            .bind(item_id)
+            .bind(&fingerprint.marketplace_id)
+            .bind(&fingerprint.title)
+            .bind(&fingerprint.price)
+            .bind(&fingerprint.seller_id)
+            .bind(&fingerprint.seller_username)
+            .bind(fingerprint.picture_hash)
+            .execute(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to upsert the fingerprint of item `{item_id}`"))?;
+
+        Ok(())
+    }
+
+    /// Fetch the fingerprints of the items, which the chat was notified about since the timestamp.
+    #[instrument(skip_all, fields(chat_id = chat_id, since = ?since))]
+    pub async fn fetch_notified_since(
+        &mut self,
+        chat_id: i64,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Fingerprint>> {
+        // language=sql
+        const QUERY: &str = "
+            SELECT item_fingerprints.* FROM notifications
+            INNER JOIN item_fingerprints ON item_fingerprints.item_id = notifications.item_id
+            WHERE notifications.chat_id = ?1 AND notifications.notified_at >= ?2
+        ";
+        sqlx::query_as(QUERY).bind(chat_id).bind(since).fetch_all(&mut *self.0).await.with_context(
+            || format!("failed to fetch the notified fingerprints of chat #{chat_id}"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::db::{Db, Item, Items, Notification, Notifications};
+
+    #[tokio::test]
+    async fn fetch_notified_since_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        Items(&mut connection).upsert(&Item::test("m42")).await?;
+
+        let fingerprint = Fingerprint {
+            marketplace_id: "marktplaats".to_string(),
+            title: "pro u6 unifi".to_string(),
+            price: Some("95".to_string()),
+            seller_id: "23640587".to_string(),
+            seller_username: "pavel".to_string(),
+            picture_hash: Some(-42),
+        };
+        ItemFingerprints(&mut connection).upsert("m42", &fingerprint).await?;
+        ItemFingerprints(&mut connection).upsert("m42", &fingerprint).await?; // verify conflicts
+        assert_eq!(
+            ItemFingerprints(&mut connection).fetch("m42").await?,
+            Some(fingerprint.clone())
+        );
+
+        let since = Utc::now() - TimeDelta::hours(1);
+        assert!(
+            ItemFingerprints(&mut connection).fetch_notified_since(42, since).await?.is_empty()
+        );
+
+        Notifications(&mut connection)
+            .upsert(&Notification { item_id: "m42".to_string(), chat_id: 42 })
+            .await?;
+        assert_eq!(
+            ItemFingerprints(&mut connection).fetch_notified_since(42, since).await?,
+            [fingerprint],
+        );
+        assert!(
+            ItemFingerprints(&mut connection).fetch_notified_since(43, since).await?.is_empty()
+        );
+
+        Ok(())
+    }
+}
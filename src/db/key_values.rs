@@ -6,33 +6,38 @@ use crate::prelude::*;
 pub struct KeyValues<'a>(pub &'a mut SqliteConnection);
 
 impl KeyValues<'_> {
-    #[instrument(skip_all, fields(key = M::KEY, value = ?value))]
-    pub async fn upsert<M: KeyedMessage>(&mut self, value: &M) -> Result {
+    #[instrument(skip_all, fields(key = key, value = ?value))]
+    pub async fn upsert_as<M: Message>(&mut self, key: &str, value: &M) -> Result {
         // language=sql
         const QUERY: &str = "
             INSERT INTO key_values (key, value) VALUES (?1, ?2)
             ON CONFLICT DO UPDATE SET value = ?2
         ";
         sqlx::query(QUERY)
-            .bind(M::KEY)
+            .bind(key)
             .bind(value.encode_to_vec())
             .execute(&mut *self.0)
             .await
-            .context("failed to upsert the subscription")?;
+            .with_context(|| format!("failed to upsert the value for key `{key}`"))?;
 
         Ok(())
     }
 
-    #[instrument(skip_all, fields(key = V::KEY), ret(level = Level::TRACE))]
     pub async fn fetch<V: Default + KeyedMessage>(&mut self) -> Result<Option<V>> {
+        self.fetch_as(V::KEY).await
+    }
+
+    /// Fetch the value stored under the explicit key instead of [`KeyedMessage::KEY`].
+    #[instrument(skip_all, fields(key = key), ret(level = Level::TRACE))]
+    pub async fn fetch_as<V: Default + Message>(&mut self, key: &str) -> Result<Option<V>> {
         // language=sql
         const QUERY: &str = "SELECT value FROM key_values WHERE key = ?1";
 
         let value: Option<Vec<u8>> = sqlx::query_scalar(QUERY)
-            .bind(V::KEY)
+            .bind(key)
             .fetch_optional(&mut *self.0)
             .await
-            .with_context(|| format!("failed to fetch the value for key `{}`", V::KEY))?;
+            .with_context(|| format!("failed to fetch the value for key `{key}`"))?;
         value.map_or_else(
             || Ok(None),
             |value| V::decode(value.as_slice()).context("failed to decode the value").map(Some),
@@ -54,16 +59,21 @@ mod tests {
     #[tokio::test]
     async fn crud_ok() -> Result {
         let db = Db::try_new(Path::new(":memory:")).await?;
-        let mut connection = db.connection().await;
+        let mut connection = db.connection().await?;
         let mut key_values = KeyValues(&mut connection);
 
         assert!(key_values.fetch::<VintedAuthenticationTokens>().await?.is_none());
 
         let tokens =
             VintedAuthenticationTokens::builder().access("access").refresh("refresh").build();
-        key_values.upsert(&tokens).await?;
+        key_values.upsert_as(VintedAuthenticationTokens::KEY, &tokens).await?;
         assert_eq!(key_values.fetch().await?, Some(tokens));
 
+        let tokens = VintedAuthenticationTokens::builder().access("be").refresh("be").build();
+        key_values.upsert_as("be", &tokens).await?;
+        assert_eq!(key_values.fetch_as("be").await?, Some(tokens));
+        assert_ne!(key_values.fetch::<VintedAuthenticationTokens>().await?.unwrap().access, "be");
+
         Ok(())
     }
 }
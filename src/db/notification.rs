@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use sqlx::SqliteConnection;
 
 use crate::prelude::*;
@@ -16,10 +17,11 @@ impl Notifications<'_> {
     pub async fn upsert(&mut self, notification: &Notification) -> Result {
         sqlx::query(
             // language=sql
-            "INSERT INTO notifications (item_id, chat_id) VALUES (?1, ?2) ON CONFLICT DO NOTHING",
+            "INSERT INTO notifications (item_id, chat_id, notified_at) VALUES (?1, ?2, ?3) ON CONFLICT DO NOTHING",
         )
         .bind(&notification.item_id)
         .bind(notification.chat_id)
+        .bind(Utc::now())
         .execute(&mut *self.0)
         .await
         .context("failed to upsert the notification")?;
@@ -27,6 +29,31 @@ impl Notifications<'_> {
         Ok(())
     }
 
+    /// Record the duplicate, which was shown in the notification of the other item.
+    #[instrument(
+        skip_all,
+        fields(item_id = notification.item_id, chat_id = notification.chat_id, grouped_into = grouped_into),
+    )]
+    pub async fn upsert_grouped(
+        &mut self,
+        notification: &Notification,
+        grouped_into: &str,
+    ) -> Result {
+        sqlx::query(
+            // language=sql
+            "INSERT INTO notifications (item_id, chat_id, notified_at, grouped_into) VALUES (?1, ?2, ?3, ?4) ON CONFLICT DO NOTHING",
+        )
+        .bind(&notification.item_id)
+        .bind(notification.chat_id)
+        .bind(Utc::now())
+        .bind(grouped_into)
+        .execute(&mut *self.0)
+        .await
+        .context("failed to upsert the grouped notification")?;
+
+        Ok(())
+    }
+
     #[instrument(skip_all, fields(item_id = notification.item_id, chat_id = notification.chat_id))]
     pub async fn exists(&mut self, notification: &Notification) -> Result<bool> {
         // language=sql
@@ -39,13 +66,28 @@ impl Notifications<'_> {
             .await
             .context("failed to check for existence of notification")
     }
+
+    /// Count the notifications sent to the chat since the specified timestamp.
+    ///
+    /// The grouped duplicates are not counted, they were sent within the other notification.
+    #[instrument(skip_all, fields(chat_id = chat_id, since = ?since))]
+    pub async fn count_since(&mut self, chat_id: i64, since: DateTime<Utc>) -> Result<u32> {
+        // language=sql
+        const QUERY: &str = "SELECT COUNT(*) FROM notifications WHERE chat_id = ?1 AND notified_at >= ?2 AND grouped_into IS NULL";
+        sqlx::query_scalar(QUERY)
+            .bind(chat_id)
+            .bind(since)
+            .fetch_one(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to count notifications of chat #{chat_id}"))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::Path;
 
-    use chrono::Utc;
+    use chrono::TimeDelta;
 
     use super::*;
     use crate::db::{
@@ -56,10 +98,9 @@ mod tests {
     #[tokio::test]
     async fn test_exists_ok() -> Result {
         let db = Db::try_new(Path::new(":memory:")).await?;
-        let mut connection = db.connection().await;
+        let mut connection = db.connection().await?;
 
-        let item = Item { id: "m42", updated_at: Utc::now() };
-        Items(&mut connection).upsert(item).await?;
+        Items(&mut connection).upsert(&Item::test("m42")).await?;
 
         let notification_1 = Notification { item_id: "m42".to_string(), chat_id: 42 };
 
@@ -73,4 +114,47 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_count_since_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+
+        let since = Utc::now() - TimeDelta::hours(1);
+        Items(&mut connection).upsert(&Item::test("m42")).await?;
+        Items(&mut connection).upsert(&Item::test("m43")).await?;
+
+        let mut notifications = Notifications(&mut connection);
+        notifications.upsert(&Notification { item_id: "m42".to_string(), chat_id: 42 }).await?;
+        notifications.upsert(&Notification { item_id: "m43".to_string(), chat_id: 42 }).await?;
+        notifications.upsert(&Notification { item_id: "m43".to_string(), chat_id: 43 }).await?;
+
+        assert_eq!(notifications.count_since(42, since).await?, 2);
+        assert_eq!(notifications.count_since(42, Utc::now() + TimeDelta::hours(1)).await?, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_count_since_grouped_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+
+        let since = Utc::now() - TimeDelta::hours(1);
+        for item_id in ["m42", "m43", "m44"] {
+            Items(&mut connection).upsert(&Item::test(item_id)).await?;
+        }
+
+        // The group of three items is sent as one notification:
+        let mut notifications = Notifications(&mut connection);
+        notifications.upsert(&Notification { item_id: "m42".to_string(), chat_id: 42 }).await?;
+        for item_id in ["m43", "m44"] {
+            let notification = Notification { item_id: item_id.to_string(), chat_id: 42 };
+            notifications.upsert_grouped(&notification, "m42").await?;
+            assert!(notifications.exists(&notification).await?);
+        }
+        assert_eq!(notifications.count_since(42, since).await?, 1);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,248 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, Row, SqliteConnection, sqlite::SqliteRow};
+use url::Url;
+
+use crate::prelude::*;
+
+/// Pending item notification, or a plain message, in the outbox.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutboxEntry {
+    /// Row ID, assigned by [`Outbox::insert`].
+    pub id: i64,
+
+    /// Notified item, or `None` for a plain message.
+    pub item_id: Option<String>,
+
+    pub chat_id: i64,
+
+    /// Rendered HTML text.
+    pub text: String,
+
+    pub picture_urls: Vec<Url>,
+
+    /// Number of the delivery attempts so far.
+    pub n_attempts: u32,
+
+    pub next_attempt_at: DateTime<Utc>,
+}
+
+impl FromRow<'_, SqliteRow> for OutboxEntry {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let picture_urls: String = row.try_get("picture_urls")?;
+        Ok(Self {
+            id: row.try_get("id")?,
+            item_id: row.try_get("item_id")?,
+            chat_id: row.try_get("chat_id")?,
+            text: row.try_get("text")?,
+            picture_urls: serde_json::from_str(&picture_urls)
+                .map_err(|error| sqlx::Error::Decode(error.into()))?,
+            n_attempts: row.try_get("n_attempts")?,
+            next_attempt_at: row.try_get("next_attempt_at")?,
+        })
+    }
+}
+
+pub struct Outbox<'a>(pub &'a mut SqliteConnection);
+
+impl Outbox<'_> {
+    /// Put the entry into the outbox and assign its ID.
+    ///
+    /// For an item, this should happen in the same transaction as the [`super::Notification`] upsert.
+    #[instrument(skip_all, fields(item_id = entry.item_id, chat_id = entry.chat_id))]
+    pub async fn insert(&mut self, entry: &mut OutboxEntry) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            INSERT INTO outbox (item_id, chat_id, text, picture_urls, n_attempts, next_attempt_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            RETURNING id
+        ";
+        entry.id = sqlx::query_scalar(QUERY)
+            .bind(&entry.item_id)
+            .bind(entry.chat_id)
+            .bind(&entry.text)
+            .bind(serde_json::to_string(&entry.picture_urls)?)
+            .bind(entry.n_attempts)
+            .bind(entry.next_attempt_at)
+            .fetch_one(&mut *self.0)
+            .await
+            .with_context(|| {
+                format!("failed to put the entry {:?} into the outbox", entry.item_id)
+            })?;
+        Ok(())
+    }
+
+    /// Fetch the entry, which is due the earliest.
+    #[instrument(skip_all, fields(now = ?now))]
+    pub async fn next_due(&mut self, now: DateTime<Utc>) -> Result<Option<OutboxEntry>> {
+        // language=sql
+        const QUERY: &str = "
+            SELECT * FROM outbox
+            WHERE NOT is_in_flight AND next_attempt_at <= ?1
+            ORDER BY next_attempt_at
+            LIMIT 1
+        ";
+        sqlx::query_as(QUERY)
+            .bind(now)
+            .fetch_optional(&mut *self.0)
+            .await
+            .context("failed to fetch the next outbox entry")
+    }
+
+    /// Mark the entry as being delivered and count the attempt.
+    #[instrument(skip_all, fields(id = entry.id, item_id = entry.item_id, chat_id = entry.chat_id))]
+    pub async fn start_attempt(&mut self, entry: &mut OutboxEntry) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            UPDATE outbox SET is_in_flight = TRUE, n_attempts = n_attempts + 1
+            WHERE id = ?1
+        ";
+        sqlx::query(QUERY)
+            .bind(entry.id)
+            .execute(&mut *self.0)
+            .await
+            .context("failed to start the delivery attempt")?;
+        entry.n_attempts += 1;
+        Ok(())
+    }
+
+    /// Schedule another attempt after the failed one.
+    #[instrument(skip_all, fields(id = entry.id, item_id = entry.item_id, chat_id = entry.chat_id, next_attempt_at = ?next_attempt_at))]
+    pub async fn reschedule(
+        &mut self,
+        entry: &OutboxEntry,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            UPDATE outbox SET is_in_flight = FALSE, next_attempt_at = ?2
+            WHERE id = ?1
+        ";
+        sqlx::query(QUERY)
+            .bind(entry.id)
+            .bind(next_attempt_at)
+            .execute(&mut *self.0)
+            .await
+            .context("failed to reschedule the delivery")?;
+        Ok(())
+    }
+
+    /// Remove the delivered, or given up, entry.
+    #[instrument(skip_all, fields(id = entry.id, item_id = entry.item_id, chat_id = entry.chat_id))]
+    pub async fn delete(&mut self, entry: &OutboxEntry) -> Result {
+        // language=sql
+        const QUERY: &str = "DELETE FROM outbox WHERE id = ?1";
+        sqlx::query(QUERY)
+            .bind(entry.id)
+            .execute(&mut *self.0)
+            .await
+            .context("failed to delete the outbox entry")?;
+        Ok(())
+    }
+
+    /// Drop the entries, which were in flight when the previous process stopped.
+    ///
+    /// They may or may not have been delivered, and we never risk sending them twice.
+    ///
+    /// # Returns
+    ///
+    /// Number of the dropped entries.
+    #[instrument(skip_all)]
+    pub async fn drop_in_flight(&mut self) -> Result<u64> {
+        // language=sql
+        const QUERY: &str = "DELETE FROM outbox WHERE is_in_flight";
+        Ok(sqlx::query(QUERY)
+            .execute(&mut *self.0)
+            .await
+            .context("failed to drop the in-flight outbox entries")?
+            .rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::db::{Db, Item, Items};
+
+    #[tokio::test]
+    async fn delivery_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        Items(&mut connection).upsert(&Item::test("m42")).await?;
+        Items(&mut connection).upsert(&Item::test("m43")).await?;
+
+        let now = Utc::now();
+        let mut first = OutboxEntry {
+            id: 0,
+            item_id: Some("m42".to_string()),
+            chat_id: 42,
+            text: "<b>Unifi</b>".to_string(),
+            picture_urls: vec![Url::parse("https://example.com/1.jpg")?],
+            n_attempts: 0,
+            next_attempt_at: now - TimeDelta::seconds(1),
+        };
+        let mut second = OutboxEntry {
+            item_id: Some("m43".to_string()),
+            picture_urls: Vec::new(),
+            next_attempt_at: now,
+            ..first.clone()
+        };
+        let mut outbox = Outbox(&mut connection);
+        outbox.insert(&mut second).await?;
+        outbox.insert(&mut first).await?;
+        assert!(
+            outbox.insert(&mut first.clone()).await.is_err(),
+            "at most one entry per item and chat"
+        );
+
+        let mut entry = outbox.next_due(now).await?.unwrap();
+        assert_eq!(entry, first);
+
+        outbox.start_attempt(&mut entry).await?;
+        assert_eq!(entry.n_attempts, 1);
+        assert_eq!(outbox.next_due(now).await?, Some(second.clone()), "in-flight entry is skipped");
+
+        outbox.reschedule(&entry, now + TimeDelta::minutes(1)).await?;
+        outbox.delete(&second).await?;
+        assert_eq!(outbox.next_due(now).await?, None, "rescheduled entry is due later");
+        let mut entry = outbox.next_due(now + TimeDelta::minutes(1)).await?.unwrap();
+        assert_eq!(entry.item_id.as_deref(), Some("m42"));
+        assert_eq!(entry.n_attempts, 1);
+
+        outbox.start_attempt(&mut entry).await?;
+        assert_eq!(outbox.drop_in_flight().await?, 1);
+        assert_eq!(outbox.next_due(now + TimeDelta::days(1)).await?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn plain_messages_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+
+        let now = Utc::now();
+        let mut first = OutboxEntry {
+            id: 0,
+            item_id: None,
+            chat_id: 42,
+            text: "🔕 Quota".to_string(),
+            picture_urls: Vec::new(),
+            n_attempts: 0,
+            next_attempt_at: now - TimeDelta::seconds(1),
+        };
+        let mut second = OutboxEntry { next_attempt_at: now, ..first.clone() };
+        let mut outbox = Outbox(&mut connection);
+        outbox.insert(&mut first).await?;
+        outbox.insert(&mut second).await?;
+        assert_ne!(first.id, second.id);
+
+        outbox.delete(&first).await?;
+        assert_eq!(outbox.next_due(now).await?, Some(second), "only the deleted message is gone");
+
+        Ok(())
+    }
+}
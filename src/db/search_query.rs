@@ -1,12 +1,13 @@
 use std::borrow::Cow;
 
 use anyhow::Context;
+use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqliteConnection};
 
 use crate::{marketplace::NormalisedQuery, prelude::*};
 
 /// User's search query.
-#[derive(Clone, Debug, PartialEq, Eq, FromRow)]
+#[derive(Clone, Debug, PartialEq, Eq, FromRow, Serialize, Deserialize)]
 pub struct SearchQuery {
     /// [SeaHash][1] of a search query.
     ///
@@ -29,7 +30,7 @@ impl<S: AsRef<str>> From<S> for SearchQuery {
 }
 
 impl SearchQuery {
-    pub fn normalised_query(&self) -> Cow<NormalisedQuery> {
+    pub fn normalised_query(&self) -> Cow<'_, NormalisedQuery> {
         Cow::Owned(NormalisedQuery::parse(&self.text))
     }
 }
@@ -74,7 +75,7 @@ mod tests {
     #[tokio::test]
     async fn search_query_ok() -> Result {
         let db = Db::try_new(Path::new(":memory:")).await?;
-        let mut connection = db.connection().await;
+        let mut connection = db.connection().await?;
         let mut search_queries = SearchQueries(&mut connection);
 
         let query = SearchQuery::from("test");
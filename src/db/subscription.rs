@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqliteConnection};
 
 use crate::prelude::*;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, FromRow)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, FromRow, Serialize, Deserialize)]
 pub struct Subscription {
     pub query_hash: i64,
     pub chat_id: i64,
@@ -40,6 +41,44 @@ impl Subscriptions<'_> {
 
         Ok(())
     }
+
+    #[instrument(skip_all, fields(query_hash = subscription.query_hash, chat_id = subscription.chat_id))]
+    pub async fn exists(&mut self, subscription: Subscription) -> Result<bool> {
+        // language=sql
+        const QUERY: &str =
+            "SELECT EXISTS(SELECT 1 FROM subscriptions WHERE query_hash = ?1 AND chat_id = ?2)";
+        sqlx::query_scalar(QUERY)
+            .bind(subscription.query_hash)
+            .bind(subscription.chat_id)
+            .fetch_one(&mut *self.0)
+            .await
+            .context("failed to check for existence of the subscription")
+    }
+
+    #[instrument(skip_all, fields(chat_id = chat_id))]
+    pub async fn count_of(&mut self, chat_id: i64) -> Result<u32> {
+        // language=sql
+        const QUERY: &str = "SELECT COUNT(*) FROM subscriptions WHERE chat_id = ?1";
+        sqlx::query_scalar(QUERY)
+            .bind(chat_id)
+            .fetch_one(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to count the subscriptions of chat #{chat_id}"))
+    }
+
+    #[instrument(skip_all, fields(chat_id = chat_id))]
+    pub async fn delete_all_of(&mut self, chat_id: i64) -> Result {
+        sqlx::query(
+            // language=sql
+            "DELETE FROM subscriptions WHERE chat_id = ?1",
+        )
+        .bind(chat_id)
+        .execute(&mut *self.0)
+        .await
+        .with_context(|| format!("failed to delete the subscriptions of chat #{chat_id}"))?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -55,7 +94,7 @@ mod tests {
     #[tokio::test]
     async fn upsert_subscription_ok() -> Result {
         let db = Db::try_new(Path::new(":memory:")).await?;
-        let mut connection = db.connection().await;
+        let mut connection = db.connection().await?;
 
         let query = SearchQuery::from("test");
         SearchQueries(&mut connection).upsert(&query).await?;
@@ -65,6 +104,12 @@ mod tests {
 
         subscriptions.upsert(subscription).await?;
         subscriptions.upsert(subscription).await?; // verify conflicts
+        assert!(subscriptions.exists(subscription).await?);
+        assert_eq!(subscriptions.count_of(42).await?, 1);
+
+        subscriptions.delete_all_of(42).await?;
+        drop(connection);
+        assert!(db.subscriptions_of(42).await?.is_empty());
 
         Ok(())
     }
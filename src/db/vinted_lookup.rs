@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use sqlx::SqliteConnection;
+
+use crate::prelude::*;
+
+/// Cached Vinted name lookups, like brand or size names to their IDs, per domain.
+pub struct VintedLookups<'a>(pub &'a mut SqliteConnection);
+
+impl VintedLookups<'_> {
+    /// Fetch the cached IDs, which have been updated since the specified moment.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the name is not cached or the cache entry is stale.
+    #[instrument(skip_all, fields(domain = domain, kind = kind, name = name), ret(level = Level::TRACE))]
+    pub async fn fetch(
+        &mut self,
+        domain: &str,
+        kind: &str,
+        name: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<Vec<u64>>> {
+        // language=sql
+        const QUERY: &str = "
+            SELECT ids FROM vinted_lookups
+            WHERE domain = ?1 AND kind = ?2 AND name = ?3 AND updated_at >= ?4
+        ";
+
+        let ids: Option<String> = sqlx::query_scalar(QUERY)
+            .bind(domain)
+            .bind(kind)
+            .bind(name)
+            .bind(since)
+            .fetch_optional(&mut *self.0)
+            .await
+            .with_context(|| format!("failed to fetch the Vinted {kind} `{name}` on `{domain}`"))?;
+        ids.map(|ids| {
+            ids.split(',')
+                .filter(|id| !id.is_empty())
+                .map(|id| id.parse().with_context(|| format!("invalid cached ID: `{id}`")))
+                .collect()
+        })
+        .transpose()
+    }
+
+    #[instrument(skip_all, fields(domain = domain, kind = kind, name = name, ?ids))]
+    pub async fn upsert(&mut self, domain: &str, kind: &str, name: &str, ids: &[u64]) -> Result {
+        // language=sql
+        const QUERY: &str = "
+            INSERT INTO vinted_lookups (domain, kind, name, ids, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT DO UPDATE SET ids = ?4, updated_at = ?5
+        ";
+        sqlx::query(QUERY)
+            .bind(domain)
+            .bind(kind)
+            .bind(name)
+            .bind(ids.iter().join(","))
+            .bind(Utc::now())
+            .execute(&mut *self.0)
+            .await
+            .with_context(|| {
+                format!("failed to upsert the Vinted {kind} `{name}` on `{domain}`")
+            })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::db::Db;
+
+    #[tokio::test]
+    async fn fetch_and_upsert_ok() -> Result {
+        let db = Db::try_new(Path::new(":memory:")).await?;
+        let mut connection = db.connection().await?;
+        let mut lookups = VintedLookups(&mut connection);
+        let since = Utc::now() - TimeDelta::days(1);
+
+        assert_eq!(lookups.fetch("nl", "brand", "nike", since).await?, None);
+
+        lookups.upsert("nl", "brand", "nike", &[53]).await?;
+        lookups.upsert("nl", "brand", "nike", &[53, 54]).await?; // verify conflicts
+        lookups.upsert("nl", "size", "octarine", &[]).await?;
+        assert_eq!(lookups.fetch("nl", "brand", "nike", since).await?, Some(vec![53, 54]));
+        assert_eq!(lookups.fetch("nl", "size", "octarine", since).await?, Some(vec![]));
+        assert_eq!(
+            lookups.fetch("nl", "brand", "nike", Utc::now() + TimeDelta::days(1)).await?,
+            None
+        );
+        assert_eq!(lookups.fetch("de", "brand", "nike", since).await?, None, "other domain");
+
+        Ok(())
+    }
+}
@@ -1,40 +1,66 @@
 #![doc = include_str!("../README.md")]
 
-use std::time::Duration;
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{BufReader, BufWriter, IntoInnerError, Write, stdin, stdout},
+};
 
-use clap::Parser;
+use chrono::{TimeDelta, Utc};
 use reqwest_middleware::ClientWithMiddleware;
 use secrecy::ExposeSecret;
+use tokio::sync::watch;
 
 use crate::{
-    cli::{Args, Command, RunArgs, VintedCommand},
-    db::{Db, KeyValues},
+    cli::{
+        Args,
+        Command,
+        DbCommand,
+        MarketplaceArgs,
+        OutputFormat,
+        RunArgs,
+        SearchArgs,
+        SubscriptionsCommand,
+        VintedCommand,
+    },
+    config::{Reloadable, Watcher as ConfigWatcher},
+    db::{Db, Dump, Items, KeyValues, SearchQueries, SearchQuery, Subscription, Subscriptions},
     heartbeat::Heartbeat,
     marketplace::{
+        Kleinanzeigen,
+        KleinanzeigenClient,
+        Marketplace,
+        Marketplaces,
         Marktplaats,
         MarktplaatsClient,
+        MarktplaatsSite,
         SearchBot,
         Vinted,
         VintedAuthenticationTokens,
         VintedClient,
     },
     prelude::*,
-    telegram::{Telegram, TelegramBot},
+    retention::Retention,
+    telegram::{Telegram, TelegramBot, outbox::OutboxSender, webhook::Webhook},
 };
 
 mod cli;
 mod client;
+mod config;
 mod db;
 mod heartbeat;
 mod logging;
 mod marketplace;
 mod prelude;
+mod quotas;
+mod retention;
 mod serde;
 mod telegram;
 
 fn main() -> Result {
     let dotenv_result = dotenvy::dotenv();
-    let cli = Args::parse();
+    let raw_args: Vec<OsString> = std::env::args_os().collect();
+    let cli = config::parse_args(&raw_args)?;
     let logging_guards = logging::init(cli.sentry_dsn.as_deref(), cli.logfire_token.clone())?;
     if let Err(error) = dotenv_result {
         warn!("⚠️ Could not load `.env`: {error:#}");
@@ -42,81 +68,220 @@ fn main() -> Result {
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()?
-        .block_on(async_main(cli))
+        .block_on(async_main(cli, raw_args))
         .inspect_err(|error| error!("💀 Fatal error: {error:#}"))?;
     logging_guards.try_shutdown()
 }
 
-async fn async_main(cli: Args) -> Result {
+async fn async_main(cli: Args, raw_args: Vec<OsString>) -> Result {
     let db = Db::try_new(&cli.db).await?;
     let client = client::try_new(cli.trace_requests)?;
     match cli.command {
-        Command::Run(args) => run(db, client, *args).await,
+        Command::Run(args) => run(db, client, *args, raw_args).await,
+        Command::Search(args) => search(db, client, *args).await,
         Command::Vinted { command } => manage_vinted(db, client, command).await,
+        Command::Subscriptions { command } => manage_subscriptions(db, command).await,
+        Command::Db { command } => manage_db(db, command).await,
     }
 }
 
 /// Run the bot indefinitely.
-async fn run(db: Db, client: ClientWithMiddleware, args: RunArgs) -> Result {
-    let telegram = Telegram::new(client.clone(), args.telegram.bot_token.into())?;
-    let command_builder = telegram.command_builder().await?;
+async fn run(
+    db: Db,
+    client: ClientWithMiddleware,
+    args: RunArgs,
+    raw_args: Vec<OsString>,
+) -> Result {
+    args.validate()?;
 
-    // Marktplaats connection:
-    let marktplaats = Marktplaats::builder()
-        .client(MarktplaatsClient(client.clone()))
-        .search_limit(args.marktplaats.marktplaats_search_limit)
-        .search_in_title_and_description(args.marktplaats.search_in_title_and_description)
-        .heartbeat(Heartbeat::new(client.clone(), args.marktplaats.heartbeat_url))
-        .build();
+    // Configuration watcher:
+    let (reloadable_sender, reloadable) = watch::channel(Reloadable::from(&args));
+    let config_watcher = match &args.config {
+        Some(path) => Some(
+            ConfigWatcher::builder()
+                .path(path.clone())
+                .raw_args(raw_args)
+                .config(config::read(path)?)
+                .reloadable(reloadable_sender)
+                .build(),
+        ),
+        None => None,
+    };
 
-    // Vinted connection:
-    let vinted = Vinted::builder()
-        .client(VintedClient(client.clone()))
-        .search_limit(args.vinted.vinted_search_limit)
-        .db(db.clone())
-        .heartbeat(Heartbeat::new(client.clone(), args.vinted.heartbeat_url))
-        .build();
+    let marketplaces = marketplaces(&db, &client, &args.marketplaces);
+    let telegram = Telegram::new(client.clone(), args.telegram.bot_token.into())?;
+    let command_builder = telegram.command_builder().await?;
 
     // Telegram bot:
+    let webhook = args.telegram.webhook_url.map(|url| Webhook {
+        url,
+        bind_address: args.telegram.webhook_bind_address,
+        secret_token: args
+            .telegram
+            .webhook_secret_token
+            .unwrap_or_else(Webhook::generate_secret_token),
+    });
     let telegram_bot = TelegramBot::builder()
         .telegram(telegram.clone())
-        .authorized_chat_ids(args.telegram.authorized_chat_ids.into_iter().collect())
         .db(db.clone())
-        .marktplaats(marktplaats.clone())
-        .vinted(vinted.clone())
+        .marketplaces(marketplaces.clone())
+        .reloadable(reloadable.clone())
         .poll_timeout_secs(args.telegram.poll_timeout_secs)
-        .heartbeat(Heartbeat::new(client, args.telegram.heartbeat_url))
+        .maybe_webhook(webhook)
+        .heartbeat(Heartbeat::new(client.clone(), args.telegram.heartbeat_url))
         .command_builder(command_builder.clone())
         .try_init()
         .await?;
 
+    // Outbox sender:
+    let outbox_sender = OutboxSender::builder().db(db.clone()).telegram(telegram.clone()).build();
+
+    // Retention:
+    let retention = Retention::builder()
+        .db(db.clone())
+        .maybe_period(args.retention_days.map(|days| TimeDelta::days(days.into())))
+        .build();
+
     // Search bot:
     let search_bot = SearchBot::builder()
         .db(db)
-        .search_interval(Duration::from_secs(args.search_interval_secs))
-        .marktplaats(marktplaats)
-        .vinted(vinted)
-        .telegram(telegram)
+        .reloadable(reloadable)
+        .client(client)
+        .marketplaces(marketplaces)
         .command_builder(command_builder)
         .build();
 
     // Run the bots:
-    tokio::try_join!(tokio::spawn(telegram_bot.run()), tokio::spawn(search_bot.run()))?;
+    tokio::try_join!(
+        tokio::spawn(telegram_bot.run()),
+        tokio::spawn(search_bot.run()),
+        tokio::spawn(outbox_sender.run()),
+        tokio::spawn(retention.run()),
+        tokio::spawn(async move {
+            if let Some(config_watcher) = config_watcher {
+                config_watcher.run().await;
+            }
+        }),
+    )?;
+    Ok(())
+}
+
+/// Build the marketplace registry from the arguments.
+fn marketplaces(db: &Db, client: &ClientWithMiddleware, args: &MarketplaceArgs) -> Marketplaces {
+    let marktplaats = Marktplaats::builder()
+        .client(MarktplaatsClient::new(client.clone(), MarktplaatsSite::Marktplaats))
+        .search_limit(args.marktplaats.marktplaats_search_limit)
+        .search_in_title_and_description(args.marktplaats.search_in_title_and_description)
+        .heartbeat(Heartbeat::new(client.clone(), args.marktplaats.heartbeat_url.clone()))
+        .build();
+
+    let tweedehands = Marktplaats::builder()
+        .client(MarktplaatsClient::new(
+            client.clone(),
+            if args.tweedehands.french {
+                MarktplaatsSite::DeuxiemeMain
+            } else {
+                MarktplaatsSite::TweedeHands
+            },
+        ))
+        .search_limit(args.tweedehands.search_limit)
+        .search_in_title_and_description(args.tweedehands.search_in_title_and_description)
+        .heartbeat(Heartbeat::new(client.clone(), args.tweedehands.heartbeat_url.clone()))
+        .build();
+
+    let vinted = Vinted::builder()
+        .client(VintedClient(client.clone()))
+        .domains(args.vinted.domains.clone())
+        .search_limit(args.vinted.vinted_search_limit)
+        .db(db.clone())
+        .heartbeat(Heartbeat::new(client.clone(), args.vinted.heartbeat_url.clone()))
+        .build();
+
+    let kleinanzeigen = Kleinanzeigen::builder()
+        .client(KleinanzeigenClient(client.clone()))
+        .search_limit(args.kleinanzeigen.search_limit)
+        .heartbeat(Heartbeat::new(client.clone(), args.kleinanzeigen.heartbeat_url.clone()))
+        .build();
+
+    Marketplaces::default()
+        .register(marktplaats, true)
+        .register(tweedehands, args.tweedehands.enabled)
+        .register(vinted, true)
+        .register(kleinanzeigen, args.kleinanzeigen.enabled)
+}
+
+/// Search once and print the items, including the ones the query post-filter dropped.
+async fn search(db: Db, client: ClientWithMiddleware, args: SearchArgs) -> Result {
+    let registry = marketplaces(&db, &client, &args.marketplaces);
+    let query = SearchQuery::from(args.query);
+    let selected: Vec<&dyn Marketplace> = if args.marketplace_ids.is_empty() {
+        let normalised_query = query.normalised_query();
+        registry
+            .enabled()
+            .filter(|marketplace| normalised_query.allows_marketplace(marketplace.id()))
+            .collect()
+    } else {
+        args.marketplace_ids
+            .iter()
+            .map(|id| registry.get(id).with_context(|| format!("unknown marketplace `{id}`")))
+            .collect::<Result<_>>()?
+    };
+
+    let now = Utc::now();
+    let mut reports = Vec::new();
+    let mut stdout = stdout().lock();
+    for marketplace in selected {
+        let mut outcome = match marketplace.search_explained(&query).await {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                error!("‼️ Failed to search on {}: {error:#}", marketplace.name());
+                continue;
+            }
+        };
+        if let Some(limit) = args.limit {
+            outcome.items.truncate(limit);
+        }
+        if args.json {
+            reports.push(serde_json::json!({
+                "marketplace": marketplace.id(),
+                "items": outcome.items.iter().map(|item| db::Item::seen(item, now)).collect::<Vec<_>>(),
+                "dropped": outcome.dropped,
+            }));
+        } else {
+            writeln!(stdout, "{}:", marketplace.name())?;
+            for item in &outcome.items {
+                let price = item
+                    .price
+                    .asking()
+                    .map_or_else(|| item.price.kind().to_string(), |amount| amount.0.to_string());
+                writeln!(stdout, "  ✅ {price:>12}  {}  {}", item.title, item.url)?;
+            }
+            for dropped in &outcome.dropped {
+                writeln!(stdout, "  ❌ {}  {}  ({})", dropped.title, dropped.url, dropped.reason)?;
+            }
+        }
+    }
+    if args.json {
+        serde_json::to_writer_pretty(&mut stdout, &reports)?;
+        writeln!(stdout)?;
+    }
     Ok(())
 }
 
 /// Manage Vinted settings.
 async fn manage_vinted(db: Db, client: ClientWithMiddleware, command: VintedCommand) -> Result {
     match command {
-        VintedCommand::Authenticate { refresh_token } => {
-            let tokens = VintedClient(client).refresh_token(refresh_token.expose_secret()).await?;
-            KeyValues(&mut *db.connection().await).upsert(&tokens).await?;
-            info!("✅ Succeeded, now the bot will search on Vinted as well");
+        VintedCommand::Authenticate { refresh_token, domain } => {
+            let tokens =
+                VintedClient(client).refresh_token(&domain, refresh_token.expose_secret()).await?;
+            KeyValues(&mut *db.connection().await?)
+                .upsert_as(&VintedAuthenticationTokens::key(&domain), &tokens)
+                .await?;
+            info!(%domain, "✅ Succeeded, now the bot will search on Vinted as well");
         }
 
-        VintedCommand::ShowTokens => {
-            let tokens: Option<VintedAuthenticationTokens> =
-                KeyValues(&mut *db.connection().await).fetch().await?;
+        VintedCommand::ShowTokens { domain } => {
+            let tokens = Vinted::fetch_tokens(&mut *db.connection().await?, &domain).await?;
             match tokens {
                 Some(tokens) => {
                     info!(tokens.access, tokens.refresh, "🔑");
@@ -129,3 +294,149 @@ async fn manage_vinted(db: Db, client: ClientWithMiddleware, command: VintedComm
     }
     Ok(())
 }
+
+/// Manage the chat subscriptions.
+async fn manage_subscriptions(db: Db, command: SubscriptionsCommand) -> Result {
+    match command {
+        SubscriptionsCommand::List { chat_id, format } => {
+            let subscriptions = match chat_id {
+                Some(chat_id) => db.subscriptions_of(chat_id).await?,
+                None => db.all_subscriptions().await?,
+            };
+            let mut stdout = stdout().lock();
+            match format {
+                OutputFormat::Table => {
+                    for (subscription, search_query) in subscriptions {
+                        writeln!(
+                            stdout,
+                            "{:>16}  {:>20}  {}",
+                            subscription.chat_id, subscription.query_hash, search_query.text,
+                        )?;
+                    }
+                }
+                OutputFormat::Json => {
+                    let subscriptions: Vec<_> = subscriptions
+                        .into_iter()
+                        .map(|(subscription, search_query)| {
+                            serde_json::json!({
+                                "chat_id": subscription.chat_id,
+                                "query_hash": subscription.query_hash,
+                                "query": search_query.text,
+                            })
+                        })
+                        .collect();
+                    serde_json::to_writer_pretty(&mut stdout, &subscriptions)?;
+                    writeln!(stdout)?;
+                }
+            }
+        }
+
+        SubscriptionsCommand::Add { chat_id, queries } => {
+            let mut transaction = db.begin().await?;
+            for query in queries {
+                let search_query = SearchQuery::from(query);
+                SearchQueries(&mut transaction).upsert(&search_query).await?;
+                let subscription = Subscription { query_hash: search_query.hash, chat_id };
+                Subscriptions(&mut transaction).upsert(subscription).await?;
+                info!(chat_id, search_query.text, "➕ Subscribed");
+            }
+            transaction.commit().await.context("failed to commit the subscriptions")?;
+        }
+
+        SubscriptionsCommand::Remove { chat_id, queries } => {
+            let mut transaction = db.begin().await?;
+            for query in queries {
+                let search_query = SearchQuery::from(query);
+                let subscription = Subscription { query_hash: search_query.hash, chat_id };
+                if Subscriptions(&mut transaction).exists(subscription).await? {
+                    Subscriptions(&mut transaction).delete(subscription).await?;
+                    info!(chat_id, search_query.text, "➖ Unsubscribed");
+                } else {
+                    warn!(chat_id, search_query.text, "⚠️ The chat is not subscribed");
+                }
+            }
+            transaction.commit().await.context("failed to commit the unsubscriptions")?;
+        }
+
+        SubscriptionsCommand::Move { chat_id, to_chat_id, queries } => {
+            let hashes: Vec<i64> =
+                queries.into_iter().map(|query| SearchQuery::from(query).hash).collect();
+            let subscriptions = db.subscriptions_of(chat_id).await?;
+            let mut transaction = db.begin().await?;
+            for (subscription, search_query) in subscriptions {
+                if !hashes.is_empty() && !hashes.contains(&subscription.query_hash) {
+                    continue;
+                }
+                Subscriptions(&mut transaction)
+                    .upsert(Subscription { chat_id: to_chat_id, ..subscription })
+                    .await?;
+                Subscriptions(&mut transaction).delete(subscription).await?;
+                info!(chat_id, to_chat_id, search_query.text, "🔀 Moved");
+            }
+            transaction.commit().await.context("failed to commit the move")?;
+        }
+    }
+    Ok(())
+}
+
+/// Maintain the database.
+async fn manage_db(db: Db, command: DbCommand) -> Result {
+    match command {
+        DbCommand::Prune { retention_days, dry_run } => {
+            let last_seen_before = Utc::now() - TimeDelta::days(retention_days.into());
+            let pruned =
+                Items(&mut *db.connection().await?).prune(last_seen_before, dry_run).await?;
+            if dry_run {
+                info!(pruned.n_items, pruned.n_notifications, %last_seen_before, "🔍 Would prune");
+            } else {
+                info!(pruned.n_items, pruned.n_notifications, %last_seen_before, "🧹 Pruned");
+                db.incremental_vacuum().await?;
+            }
+        }
+
+        DbCommand::Export { path } => {
+            let dump = {
+                let mut transaction = db.begin().await?;
+                Dump::export(&mut transaction).await?
+            };
+            match &path {
+                Some(path) => {
+                    let mut writer = BufWriter::new(File::create(path)?);
+                    serde_json::to_writer_pretty(&mut writer, &dump)?;
+                    writer
+                        .into_inner()
+                        .map_err(IntoInnerError::into_error)?
+                        .sync_all()
+                        .with_context(|| format!("failed to write `{}`", path.display()))?;
+                }
+                None => serde_json::to_writer_pretty(stdout().lock(), &dump)?,
+            }
+            info!(
+                n_subscriptions = dump.subscriptions.len(),
+                n_notifications = dump.notifications.len(),
+                "📤 Exported",
+            );
+        }
+
+        DbCommand::Import { path } => {
+            let dump: Dump = match &path {
+                Some(path) => serde_json::from_reader(BufReader::new(File::open(path)?))?,
+                None => serde_json::from_reader(stdin().lock())?,
+            };
+            let mut transaction = db.begin().await?;
+            dump.import(&mut transaction).await?;
+            transaction.commit().await.context("failed to commit the import")?;
+            info!(
+                n_subscriptions = dump.subscriptions.len(),
+                n_notifications = dump.notifications.len(),
+                "📥 Imported",
+            );
+        }
+
+        DbCommand::Backup { path } => {
+            db.backup(&path).await?;
+            info!(path = %path.display(), "💾 Backed up");
+        }
+    }
+    Ok(())
+}
@@ -1,29 +1,49 @@
 //! Generic and shared stuff for different marketplace.
 
 pub mod item;
+mod kleinanzeigen;
 mod marktplaats;
+mod registry;
 mod search;
 mod search_bot;
 mod vinted;
 
-use std::any::type_name;
-
 use async_trait::async_trait;
+use serde::Serialize;
+use url::Url;
 
 pub use self::{
-    marktplaats::{Marktplaats, MarktplaatsClient},
-    search::NormalisedQuery,
+    kleinanzeigen::{Kleinanzeigen, KleinanzeigenClient},
+    marktplaats::{Marktplaats, MarktplaatsClient, Site as MarktplaatsSite},
+    registry::Marketplaces,
+    search::{Mismatch, NormalisedQuery},
     search_bot::SearchBot,
-    vinted::{AuthenticationTokens as VintedAuthenticationTokens, Vinted, VintedClient},
+    vinted::{
+        AuthenticationTokens as VintedAuthenticationTokens,
+        Vinted,
+        VintedClient,
+        VintedDomain,
+        VintedFilters,
+    },
+};
+use crate::{
+    db::SearchQuery,
+    marketplace::item::{Details, Item},
+    prelude::*,
 };
-use crate::{db::SearchQuery, marketplace::item::Item, prelude::*};
 
 #[async_trait]
-pub trait Marketplace {
+pub trait Marketplace: Send + Sync {
+    /// Stable marketplace ID, which never changes between releases.
+    fn id(&self) -> &'static str;
+
+    /// Human-readable marketplace name.
+    fn name(&self) -> &'static str;
+
     async fn check_in(&self);
 
     async fn search_and_extend_infallible(
-        &mut self,
+        &self,
         query: &SearchQuery,
         limit: Option<usize>,
         into: &mut Vec<Item>,
@@ -36,10 +56,54 @@ pub trait Marketplace {
                 into.extend(items);
             }
             Err(error) => {
-                error!("‼️ Failed to search on {}: {error:#}", type_name::<Self>());
+                error!("‼️ Failed to search on {}: {error:#}", self.name());
             }
         }
     }
 
-    async fn search(&mut self, query: &SearchQuery) -> Result<Vec<Item>>;
+    /// Search for the items, which match the query, and check in the heartbeat.
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<Item>> {
+        let outcome = self.search_explained(query).await?;
+        self.check_in().await;
+        Ok(outcome.items)
+    }
+
+    /// Search for the items, also returning the listings, which the query post-filter dropped.
+    async fn search_explained(&self, query: &SearchQuery) -> Result<SearchOutcome>;
+
+    /// Whether the search results are incomplete without the details,
+    /// so that an item is skipped when its details fail to fetch.
+    fn requires_details(&self) -> bool {
+        false
+    }
+
+    /// Fetch the item details, which the search results lack.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the marketplace has nothing to add.
+    async fn fetch_details(&self, _item: &Item) -> Result<Option<Details>> {
+        Ok(None)
+    }
+}
+
+/// Search results along with the listings, which the query post-filter dropped.
+#[derive(Default)]
+pub struct SearchOutcome {
+    pub items: Vec<Item>,
+    pub dropped: Vec<Dropped>,
+}
+
+/// Listing, which the query post-filter dropped.
+#[derive(Serialize)]
+pub struct Dropped {
+    pub title: String,
+    pub url: Url,
+    pub reason: Mismatch,
+}
+
+impl Dropped {
+    pub fn new(item: Item, reason: Mismatch) -> Self {
+        Self { title: item.title, url: item.url, reason }
+    }
 }
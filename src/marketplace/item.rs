@@ -1,18 +1,24 @@
 use bon::Builder;
+use chrono::{DateTime, Utc};
 use url::Url;
 
 pub use self::{
     amount::Amount,
     condition::{Condition, New, Used},
     delivery::Delivery,
+    details::Details,
+    fingerprint::Fingerprint,
     location::{GeoLocation, Location},
     price::Price,
-    seller::Seller,
+    seller::{Rating, Seller},
 };
+use crate::db::SearchQuery;
 
 mod amount;
 mod condition;
 mod delivery;
+mod details;
+pub mod fingerprint;
 mod location;
 mod price;
 mod seller;
@@ -21,13 +27,36 @@ mod seller;
 #[derive(Builder)]
 pub struct Item {
     pub id: String,
+
+    /// ID of the marketplace, which the item comes from.
+    pub marketplace_id: &'static str,
+
     pub url: Url,
     pub title: String,
     pub description: Option<String>,
-    pub picture_url: Option<Url>,
+
+    #[builder(default)]
+    pub picture_urls: Vec<Url>,
+
+    pub posted_at: Option<DateTime<Utc>>,
     pub condition: Option<Condition>,
     pub delivery: Option<Delivery>,
     pub price: Price,
-    pub seller: Seller,
+
+    /// Seller, if the marketplace provides it in the search results or the details.
+    pub seller: Option<Seller>,
     pub location: Option<Location>,
 }
+
+impl Item {
+    /// Pictures to send: either all of them, or only the main one.
+    pub fn pictures(&self, all: bool) -> &[Url] {
+        if all { &self.picture_urls } else { &self.picture_urls[..self.picture_urls.len().min(1)] }
+    }
+
+    /// Search query, which follows the item's seller on the item's marketplace.
+    pub fn seller_query(&self) -> Option<SearchQuery> {
+        let seller_id = self.seller.as_ref()?.id.as_deref()?;
+        Some(SearchQuery::from(format!("site:{} seller:{seller_id}", self.marketplace_id)))
+    }
+}
@@ -1,11 +1,31 @@
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Condition {
     New(New),
     Used(Used),
     Refurbished,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+impl Condition {
+    /// Stable name of the condition, which is stored in the database.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::New(New::Unspecified) => "new",
+            Self::New(New::WithoutTags) => "new_without_tags",
+            Self::New(New::WithTags) => "new_with_tags",
+            Self::New(New::AsGood) => "as_good_as_new",
+            Self::Used(Used::Unspecified) => "used",
+            Self::Used(Used::VeryGood) => "very_good",
+            Self::Used(Used::Good) => "good",
+            Self::Used(Used::Satisfactory) => "satisfactory",
+            Self::Used(Used::NotFullyFunctional) => "not_fully_functional",
+            Self::Refurbished => "refurbished",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum New {
     Unspecified,
     WithoutTags,
@@ -13,7 +33,7 @@ pub enum New {
     AsGood,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Used {
     Unspecified,
     VeryGood,
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::marketplace::item::{Condition, Item, Location, Seller, seller::Rating};
+
+/// Item details, which are missing from the search results and fetched separately.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Details {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub picture_urls: Vec<Url>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub posted_at: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seller: Option<Seller>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seller_rating: Option<Rating>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+}
+
+impl Item {
+    /// Fill in the item with the fetched details, keeping what the details lack.
+    pub fn apply(&mut self, details: Details) {
+        if details.description.is_some() {
+            self.description = details.description;
+        }
+        if !details.picture_urls.is_empty() {
+            self.picture_urls = details.picture_urls;
+        }
+        if details.location.is_some() {
+            self.location = details.location;
+        }
+        if details.posted_at.is_some() {
+            self.posted_at = details.posted_at;
+        }
+        if details.seller.is_some() {
+            self.seller = details.seller;
+        }
+        if let (Some(seller), Some(rating)) = (&mut self.seller, details.seller_rating) {
+            seller.rating = Some(rating);
+        }
+        if details.condition.is_some() {
+            self.condition = details.condition;
+        }
+    }
+}
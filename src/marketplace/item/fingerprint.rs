@@ -0,0 +1,185 @@
+//! Fuzzy item fingerprint for detecting the same item listed several times.
+
+use image::{DynamicImage, imageops::FilterType};
+use itertools::Itertools;
+use reqwest_middleware::ClientWithMiddleware;
+use sqlx::FromRow;
+use url::Url;
+
+use crate::{
+    marketplace::item::{Item, Seller},
+    prelude::*,
+};
+
+/// Maximal Hamming distance between the picture hashes of the same picture.
+///
+/// Marketplaces re-encode and resize the pictures, so the hashes are rarely exactly the same.
+const MAX_PICTURE_DISTANCE: u32 = 6;
+
+/// Minimal number of the matching signals to consider the items the same.
+const MIN_MATCHING_SIGNALS: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq, FromRow)]
+pub struct Fingerprint {
+    pub marketplace_id: String,
+
+    /// Normalised title, see [`normalise_title`].
+    pub title: String,
+
+    /// Normalised asking price, if any.
+    pub price: Option<String>,
+
+    /// See [`crate::marketplace::item::Seller::key`].
+    pub seller_id: String,
+
+    /// Lowercase seller username, which is often the same on different marketplaces.
+    pub seller_username: String,
+
+    /// [Difference hash][1] of the main picture.
+    ///
+    /// [1]: https://www.hackerfactor.com/blog/index.php?/archives/529-Kind-of-Like-That.html
+    pub picture_hash: Option<i64>,
+}
+
+impl Fingerprint {
+    pub fn of(item: &Item, picture_hash: Option<u64>) -> Self {
+        let price = item.price.asking().map(|amount| amount.0.normalize().to_string());
+        Self {
+            marketplace_id: item.marketplace_id.to_string(),
+            title: normalise_title(&item.title),
+            price,
+            seller_id: item.seller.as_ref().map(Seller::key).unwrap_or_default().to_string(),
+            seller_username: item
+                .seller
+                .as_ref()
+                .map(|seller| seller.username.to_lowercase())
+                .unwrap_or_default(),
+            #[expect(clippy::cast_possible_wrap)]
+            picture_hash: picture_hash.map(|hash| hash as i64),
+        }
+    }
+
+    /// Check whether the fingerprints likely belong to the same item.
+    ///
+    /// The signals are the title, price, seller, and main picture.
+    /// A relisted item usually keeps everything but the ID, and a cross-listed item
+    /// usually keeps the title, price and pictures, but not necessarily the seller name.
+    pub fn is_duplicate_of(&self, other: &Self) -> bool {
+        // An unknown seller is empty, and it does not match another unknown seller:
+        let is_same_seller = (self.marketplace_id == other.marketplace_id
+            && !self.seller_id.is_empty()
+            && self.seller_id == other.seller_id)
+            || (!self.seller_username.is_empty() && self.seller_username == other.seller_username);
+        let is_same_picture = match (self.picture_hash, other.picture_hash) {
+            #[expect(clippy::cast_sign_loss)]
+            (Some(lhs), Some(rhs)) => ((lhs ^ rhs) as u64).count_ones() <= MAX_PICTURE_DISTANCE,
+            _ => false,
+        };
+        let n_matching_signals = [
+            self.title == other.title,
+            self.price.is_some() && self.price == other.price,
+            is_same_seller,
+            is_same_picture,
+        ]
+        .into_iter()
+        .filter(|is_matching| *is_matching)
+        .count();
+        n_matching_signals >= MIN_MATCHING_SIGNALS
+    }
+}
+
+/// Lowercase the title and sort its unique words, ignoring the punctuation.
+pub fn normalise_title(title: &str) -> String {
+    title
+        .split(|char: char| !char.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .sorted()
+        .dedup()
+        .join(" ")
+}
+
+/// Download the picture and calculate its difference hash.
+#[instrument(skip_all, fields(url = %url))]
+pub async fn fetch_picture_hash(client: &ClientWithMiddleware, url: &Url) -> Result<u64> {
+    let bytes = client.get(url.clone()).send().await?.error_for_status()?.bytes().await?;
+    let image = image::load_from_memory(&bytes).context("failed to decode the picture")?;
+    Ok(difference_hash(&image))
+}
+
+/// Calculate the 64-bit difference hash: whether each pixel is brighter than its right neighbour
+/// on the 9×8 grayscale thumbnail.
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let thumbnail = image.resize_exact(9, 8, FilterType::Triangle).into_luma8();
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if thumbnail.get_pixel(x, y).0[0] > thumbnail.get_pixel(x + 1, y).0[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+
+    fn fingerprint() -> Fingerprint {
+        Fingerprint {
+            marketplace_id: "marktplaats".to_string(),
+            title: normalise_title("Ubiquiti UniFi U6 Pro"),
+            price: Some("95".to_string()),
+            seller_id: "23640587".to_string(),
+            seller_username: "pavel".to_string(),
+            picture_hash: Some(0x0F0F_0F0F_0F0F_0F0F),
+        }
+    }
+
+    #[test]
+    fn normalise_title_ok() {
+        assert_eq!(normalise_title("UniFi U6-Pro, Ubiquiti (unifi)"), "pro u6 ubiquiti unifi");
+    }
+
+    #[test]
+    fn relisted_is_duplicate_ok() {
+        let relisted = Fingerprint { picture_hash: None, ..fingerprint() };
+        assert!(relisted.is_duplicate_of(&fingerprint()));
+    }
+
+    #[test]
+    fn cross_listed_is_duplicate_ok() {
+        let cross_listed = Fingerprint {
+            marketplace_id: "vinted".to_string(),
+            seller_id: "258360251".to_string(),
+            seller_username: "hertokken".to_string(),
+            picture_hash: Some(0x0F0F_0F0F_0F0F_0F0E),
+            ..fingerprint()
+        };
+        assert!(cross_listed.is_duplicate_of(&fingerprint()));
+    }
+
+    #[test]
+    fn same_seller_other_item_is_not_duplicate_ok() {
+        let other = Fingerprint {
+            title: normalise_title("UniFi Switch Lite 8 PoE"),
+            price: Some("80".to_string()),
+            picture_hash: Some(0x7777_0000_7777_0000),
+            ..fingerprint()
+        };
+        assert!(!other.is_duplicate_of(&fingerprint()));
+    }
+
+    #[test]
+    fn difference_hash_ok() {
+        let gradient = GrayImage::from_fn(90, 80, |x, _| Luma([255 - u8::try_from(x).unwrap()]));
+        assert_eq!(difference_hash(&DynamicImage::ImageLuma8(gradient)), u64::MAX);
+
+        let flat = GrayImage::from_pixel(90, 80, Luma([128]));
+        assert_eq!(difference_hash(&DynamicImage::ImageLuma8(flat)), 0);
+    }
+}
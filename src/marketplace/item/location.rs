@@ -1,12 +1,13 @@
 use bon::Builder;
+use serde::{Deserialize, Serialize};
 
-#[derive(Builder)]
+#[derive(Clone, Debug, PartialEq, Builder, Serialize, Deserialize)]
 pub struct Location {
     pub toponym: String,
     pub geo: Option<GeoLocation>,
 }
 
-#[derive(Copy, Clone, Builder)]
+#[derive(Copy, Clone, Debug, PartialEq, Builder, Serialize, Deserialize)]
 pub struct GeoLocation {
     pub latitude: f64,
     pub longitude: f64,
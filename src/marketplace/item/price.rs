@@ -12,3 +12,46 @@ pub enum Price {
     FastBid,
     Exchange,
 }
+
+impl Price {
+    /// Stable name of the price kind, which is stored in the database.
+    pub const fn kind(self) -> &'static str {
+        match self {
+            Self::Fixed(_) => "fixed",
+            Self::OnRequest => "on_request",
+            Self::MinimalBid(_) => "minimal_bid",
+            Self::MaximalBid(_) => "maximal_bid",
+            Self::SeeDescription => "see_description",
+            Self::ToBeAgreed => "to_be_agreed",
+            Self::Reserved => "reserved",
+            Self::FastBid => "fast_bid",
+            Self::Exchange => "exchange",
+        }
+    }
+
+    /// Restore the price from its [kind][Self::kind] and [asking amount][Self::asking].
+    pub fn from_kind(kind: &str, asking: Option<Amount>) -> Option<Self> {
+        match (kind, asking) {
+            ("fixed", Some(amount)) => Some(Self::Fixed(amount)),
+            ("on_request", _) => Some(Self::OnRequest),
+            ("minimal_bid", Some(amount)) => Some(Self::MinimalBid(amount)),
+            ("maximal_bid", Some(amount)) => Some(Self::MaximalBid(amount)),
+            ("see_description", _) => Some(Self::SeeDescription),
+            ("to_be_agreed", _) => Some(Self::ToBeAgreed),
+            ("reserved", _) => Some(Self::Reserved),
+            ("fast_bid", _) => Some(Self::FastBid),
+            ("exchange", _) => Some(Self::Exchange),
+            _ => None,
+        }
+    }
+
+    /// Asking amount, if the price has one.
+    pub const fn asking(self) -> Option<Amount> {
+        match self {
+            Self::Fixed(amount) | Self::MinimalBid(amount) | Self::MaximalBid(amount) => {
+                Some(amount)
+            }
+            _ => None,
+        }
+    }
+}
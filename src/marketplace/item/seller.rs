@@ -1,8 +1,30 @@
 use bon::Builder;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
-#[derive(Builder)]
+#[derive(Clone, Debug, PartialEq, Builder, Serialize, Deserialize)]
 pub struct Seller {
+    /// Marketplace-specific seller ID, used to follow the seller.
+    #[builder(into)]
+    pub id: Option<String>,
+
     pub username: String,
     pub profile_url: Url,
+    pub rating: Option<Rating>,
+}
+
+impl Seller {
+    /// Stable key of the seller: the ID, or the username if the ID is unknown.
+    pub fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.username)
+    }
+}
+
+/// Seller's review rating.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    /// Average score out of 5.
+    pub score: f64,
+
+    pub n_reviews: u32,
 }
@@ -0,0 +1,105 @@
+mod ad;
+mod client;
+mod listing;
+
+use async_trait::async_trait;
+use bon::Builder;
+
+pub use self::client::KleinanzeigenClient;
+use crate::{
+    db::SearchQuery,
+    heartbeat::Heartbeat,
+    marketplace::{
+        Dropped,
+        Marketplace,
+        SearchOutcome,
+        item::{Details, Item, Location},
+    },
+    prelude::*,
+};
+
+/// [Kleinanzeigen](https://www.kleinanzeigen.de) marketplace.
+#[must_use]
+#[derive(Clone, Builder)]
+pub struct Kleinanzeigen {
+    client: KleinanzeigenClient,
+
+    /// Limit of the listings, which are taken after filtering the search results.
+    search_limit: u32,
+
+    heartbeat: Heartbeat,
+}
+
+#[async_trait]
+impl Marketplace for Kleinanzeigen {
+    fn id(&self) -> &'static str {
+        "kleinanzeigen"
+    }
+
+    fn name(&self) -> &'static str {
+        "Kleinanzeigen"
+    }
+
+    async fn check_in(&self) {
+        self.heartbeat.check_in().await;
+    }
+
+    async fn search_explained(&self, query: &SearchQuery) -> Result<SearchOutcome> {
+        let query = query.normalised_query();
+        let search_text = query.search_text();
+        let listings = match query.seller() {
+            Some(user_id) => self.client.seller_ads(user_id).await?,
+            None => self.client.search(&search_text).await?,
+        };
+        let n_fetched = listings.len();
+        let mut outcome = SearchOutcome::default();
+        let mut matching = Vec::new();
+        for listing in listings {
+            match query.mismatch(listing.title.split_whitespace()) {
+                None => matching.push(listing),
+                Some(reason) => {
+                    outcome.dropped.push(Dropped {
+                        title: listing.title,
+                        url: listing.url,
+                        reason,
+                    });
+                }
+            }
+        }
+        for listing in matching.into_iter().take(self.search_limit as usize) {
+            let item = Item::builder()
+                .id(format!("kleinanzeigen::{}", listing.ad_id))
+                .marketplace_id(self.id())
+                .url(listing.url)
+                .title(listing.title)
+                .maybe_description(listing.description)
+                .picture_urls(listing.picture_url.into_iter().collect())
+                .maybe_delivery(listing.delivery)
+                .price(listing.price)
+                .maybe_location(
+                    listing.location.map(|toponym| Location::builder().toponym(toponym).build()),
+                )
+                .build();
+            outcome.items.push(item);
+        }
+        info!(search_text, n_fetched, n_filtered = outcome.items.len(), "🛍️ Fetched");
+        Ok(outcome)
+    }
+
+    /// The search results lack the seller, which is only on the ad page.
+    fn requires_details(&self) -> bool {
+        true
+    }
+
+    async fn fetch_details(&self, item: &Item) -> Result<Option<Details>> {
+        let ad = self.client.fetch_ad(&item.url).await?;
+        Ok(Some(Details {
+            location: item.location.as_ref().map(|location| ad.location(location.toponym.clone())),
+            description: ad.description,
+            picture_urls: ad.picture_url.into_iter().collect(),
+            seller: Some(ad.seller),
+            condition: ad.condition,
+            ..Details::default()
+        }))
+    }
+}
@@ -0,0 +1,198 @@
+//! Advertisement page parsing.
+//!
+//! The search result page lacks the seller, so the ad page is fetched as the item details.
+
+use std::sync::LazyLock;
+
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::{
+    marketplace::{
+        item::{Condition, GeoLocation, Location, New, Seller, Used},
+        kleinanzeigen::listing::{selector, text_of},
+    },
+    prelude::*,
+};
+
+static SELLER: LazyLock<Selector> =
+    LazyLock::new(|| selector("#viewad-contact .userprofile-vip a[href]"));
+static DESCRIPTION: LazyLock<Selector> = LazyLock::new(|| selector("#viewad-description-text"));
+static DETAIL: LazyLock<Selector> = LazyLock::new(|| selector(".addetailslist--detail"));
+static DETAIL_VALUE: LazyLock<Selector> =
+    LazyLock::new(|| selector(".addetailslist--detail--value"));
+static IMAGE: LazyLock<Selector> = LazyLock::new(|| selector(r#"meta[property="og:image"]"#));
+static LATITUDE: LazyLock<Selector> = LazyLock::new(|| selector(r#"meta[property="og:latitude"]"#));
+static LONGITUDE: LazyLock<Selector> =
+    LazyLock::new(|| selector(r#"meta[property="og:longitude"]"#));
+
+/// Details from the advertisement page.
+pub struct Ad {
+    pub seller: Seller,
+    pub description: Option<String>,
+    pub condition: Option<Condition>,
+    pub picture_url: Option<Url>,
+    pub geo: Option<GeoLocation>,
+}
+
+impl Ad {
+    pub fn parse(html: &str, base_url: &Url) -> Result<Self> {
+        let html = Html::parse_document(html);
+        let seller = html.select(&SELLER).next().context("missing seller")?;
+        let profile_url = base_url
+            .join(seller.attr("href").unwrap_or_default())
+            .context("failed to parse the seller profile URL")?;
+        let condition = html
+            .select(&DETAIL)
+            .find(|detail| text_of(*detail).starts_with("Zustand"))
+            .and_then(|detail| detail.select(&DETAIL_VALUE).next())
+            .and_then(|value| parse_condition(&text_of(value)));
+        let picture_url = html
+            .select(&IMAGE)
+            .next()
+            .and_then(|image| image.attr("content"))
+            .map(Url::parse)
+            .transpose()
+            .context("failed to parse the picture URL")?;
+        let meta_f64 = |selector: &Selector| {
+            html.select(selector)
+                .next()
+                .and_then(|meta| meta.attr("content"))
+                .and_then(|content| content.parse::<f64>().ok())
+        };
+        let geo = match (meta_f64(&LATITUDE), meta_f64(&LONGITUDE)) {
+            (Some(latitude), Some(longitude)) => {
+                Some(GeoLocation::builder().latitude(latitude).longitude(longitude).build())
+            }
+            _ => None,
+        };
+        Ok(Self {
+            seller: Seller::builder()
+                .maybe_id(
+                    profile_url
+                        .query_pairs()
+                        .find(|(key, _)| key == "userId")
+                        .map(|(_, user_id)| user_id.into_owned()),
+                )
+                .username(text_of(seller))
+                .profile_url(profile_url)
+                .build(),
+            description: html.select(&DESCRIPTION).next().map(text_of),
+            condition,
+            picture_url,
+            geo,
+        })
+    }
+
+    /// Attach the coordinates to the listing's toponym.
+    pub fn location(&self, toponym: String) -> Location {
+        Location::builder().toponym(toponym).maybe_geo(self.geo).build()
+    }
+}
+
+fn parse_condition(value: &str) -> Option<Condition> {
+    match value.to_lowercase().as_str() {
+        "neu" => Some(Condition::New(New::Unspecified)),
+        "wie neu" => Some(Condition::New(New::AsGood)),
+        "sehr gut" => Some(Condition::Used(Used::VeryGood)),
+        "gut" => Some(Condition::Used(Used::Good)),
+        "in ordnung" => Some(Condition::Used(Used::Satisfactory)),
+        "defekt" => Some(Condition::Used(Used::NotFullyFunctional)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ad_3079412655_ok() -> Result {
+        let base_url = Url::parse("https://www.kleinanzeigen.de")?;
+        // language=html
+        let html = r#"
+            <!DOCTYPE html>
+            <html lang="de">
+            <head>
+                <title>Ubiquiti UniFi U6 Pro Access Point WiFi 6 in Berlin - Mitte | Netzwerk &amp; Modem gebraucht kaufen | kleinanzeigen.de</title>
+                <meta property="og:title" content="Ubiquiti UniFi U6 Pro Access Point WiFi 6"/>
+                <meta property="og:image" content="https://img.kleinanzeigen.de/api/v1/prod-ads/images/7d/7d2f4e1c-3b9a-4c6e-9f08-5a1d2c7b8e63?rule=$_59.AUTO"/>
+                <meta property="og:url" content="https://www.kleinanzeigen.de/s-anzeige/ubiquiti-unifi-u6-pro-access-point-wifi-6/3079412655-225-3487"/>
+                <meta property="og:latitude" content="52.5299"/>
+                <meta property="og:longitude" content="13.4013"/>
+                <meta property="og:locality" content="Berlin - Mitte"/>
+            </head>
+            <body>
+                <article id="viewad-product" class="vip-box">
+                    <h1 id="viewad-title" class="boxedarticle--title" itemprop="name">
+                        Ubiquiti UniFi U6 Pro Access Point WiFi 6</h1>
+                    <div class="boxedarticle--flex--container">
+                        <h2 class="boxedarticle--price" id="viewad-price">
+                            110 € VB</h2>
+                    </div>
+                    <div id="viewad-details" class="splitlinebox l-container-row">
+                        <ul class="addetailslist">
+                            <li class="addetailslist--detail">
+                                Art<span class="addetailslist--detail--value">
+                                    Netzwerk &amp; Modem</span>
+                            </li>
+                            <li class="addetailslist--detail">
+                                Zustand<span class="addetailslist--detail--value">
+                                    Sehr Gut</span>
+                            </li>
+                            <li class="addetailslist--detail">
+                                Versand<span class="addetailslist--detail--value">
+                                    Versand möglich</span>
+                            </li>
+                        </ul>
+                    </div>
+                    <div id="viewad-description" class="l-container-row">
+                        <p id="viewad-description-text" class="text-force-linebreak " itemprop="description">
+                            Verkaufe meinen U6 Pro, lief ein Jahr an der Decke im Büro.<br/>Inklusive Halterung, ohne PoE-Injektor.<br/><br/>Privatverkauf, keine Garantie oder Rücknahme.</p>
+                    </div>
+                </article>
+                <div id="viewad-contact" class="l-container-row contentbox--vip no-shadow j-sidebar-content">
+                    <div class="iconlist-text">
+                        <span class="text-body-regular-strong text-force-linebreak userprofile-vip">
+                            <a href="/s-bestandsliste.html?userId=47129506">
+                                Jonas K.</a>
+                        </span>
+                        <span class="userprofile-vip-details-text">Privater Nutzer</span>
+                        <span class="userprofile-vip-details-text">Aktiv seit 14.03.2016</span>
+                    </div>
+                </div>
+            </body>
+            </html>
+        "#;
+        let ad = Ad::parse(html, &base_url)?;
+        assert_eq!(ad.seller.id.as_deref(), Some("47129506"));
+        assert_eq!(ad.seller.username, "Jonas K.");
+        assert_eq!(
+            ad.seller.profile_url.as_str(),
+            "https://www.kleinanzeigen.de/s-bestandsliste.html?userId=47129506",
+        );
+        assert_eq!(
+            ad.description.as_deref(),
+            Some(
+                "Verkaufe meinen U6 Pro, lief ein Jahr an der Decke im Büro. Inklusive Halterung, ohne PoE-Injektor. Privatverkauf, keine Garantie oder Rücknahme."
+            ),
+        );
+        assert_eq!(ad.condition, Some(Condition::Used(Used::VeryGood)));
+        assert_eq!(
+            ad.picture_url.as_ref().map(Url::as_str),
+            Some(
+                "https://img.kleinanzeigen.de/api/v1/prod-ads/images/7d/7d2f4e1c-3b9a-4c6e-9f08-5a1d2c7b8e63?rule=$_59.AUTO"
+            ),
+        );
+        let geo = ad.geo.unwrap();
+        assert!((geo.latitude - 52.5299).abs() < f64::EPSILON);
+        assert!((geo.longitude - 13.4013).abs() < f64::EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_ad_without_seller_fails() {
+        let base_url = Url::parse("https://www.kleinanzeigen.de").unwrap();
+        assert!(Ad::parse("<html></html>", &base_url).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use reqwest_middleware::ClientWithMiddleware;
+use url::Url;
+
+use crate::{
+    marketplace::kleinanzeigen::{ad::Ad, listing::Listing},
+    prelude::*,
+};
+
+/// Kleinanzeigen web client.
+///
+/// Kleinanzeigen has no public API, so the client scrapes the web pages.
+#[must_use]
+#[derive(Clone)]
+pub struct KleinanzeigenClient(pub ClientWithMiddleware);
+
+impl KleinanzeigenClient {
+    pub fn base_url() -> Url {
+        Url::parse("https://www.kleinanzeigen.de").unwrap()
+    }
+
+    /// Search for the newest ads.
+    #[instrument(skip_all)]
+    pub async fn search(&self, query: &str) -> Result<Vec<Listing>> {
+        info!(query, "🔎 Searching…");
+        let mut url = Self::base_url().join("/s-suchanfrage.html")?;
+        url.query_pairs_mut()
+            .append_pair("keywords", query)
+            .append_pair("sortingField", "SORTING_DATE");
+        let html = self.get_html(url).await.context("failed to search")?;
+        Listing::parse_all(&html, &Self::base_url()).context("failed to parse the search results")
+    }
+
+    /// Fetch the seller's ads, which are listed in the same way as the search results.
+    #[instrument(skip_all, fields(user_id = user_id))]
+    pub async fn seller_ads(&self, user_id: &str) -> Result<Vec<Listing>> {
+        info!("🔎 Fetching the seller's ads…");
+        let mut url = Self::base_url().join("/s-bestandsliste.html")?;
+        url.query_pairs_mut().append_pair("userId", user_id);
+        let html = self.get_html(url).await.context("failed to fetch the seller's ads")?;
+        Listing::parse_all(&html, &Self::base_url()).context("failed to parse the seller's ads")
+    }
+
+    /// Fetch the advertisement page.
+    #[instrument(skip_all, fields(url = %url))]
+    pub async fn fetch_ad(&self, url: &Url) -> Result<Ad> {
+        let html = self.get_html(url.clone()).await.context("failed to fetch the ad")?;
+        Ad::parse(&html, &Self::base_url()).context("failed to parse the ad")
+    }
+
+    async fn get_html(&self, url: Url) -> Result<String> {
+        Ok(self.0.get(url).send().await?.error_for_status()?.text().await?)
+    }
+}
@@ -0,0 +1,254 @@
+//! Search result page parsing.
+
+use std::sync::LazyLock;
+
+use rust_decimal::Decimal;
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+use crate::{
+    marketplace::item::{Amount, Delivery, Price},
+    prelude::*,
+};
+
+static ARTICLE: LazyLock<Selector> = LazyLock::new(|| selector("article.aditem[data-adid]"));
+static TITLE: LazyLock<Selector> = LazyLock::new(|| selector(".aditem-main--middle h2 a"));
+static DESCRIPTION: LazyLock<Selector> =
+    LazyLock::new(|| selector(".aditem-main--middle--description"));
+static PRICE: LazyLock<Selector> =
+    LazyLock::new(|| selector(".aditem-main--middle--price-shipping--price"));
+static SHIPPING: LazyLock<Selector> =
+    LazyLock::new(|| selector(".aditem-main--middle--price-shipping--shipping"));
+static LOCATION: LazyLock<Selector> = LazyLock::new(|| selector(".aditem-main--top--left"));
+static IMAGE: LazyLock<Selector> = LazyLock::new(|| selector(".imagebox[data-imgsrc]"));
+
+/// Parse the static selector.
+pub fn selector(selectors: &str) -> Selector {
+    Selector::parse(selectors).unwrap()
+}
+
+/// Collect and normalise the element's text.
+pub fn text_of(element: ElementRef<'_>) -> String {
+    element.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ")
+}
+
+/// Advertisement from the search result page.
+pub struct Listing {
+    /// Kleinanzeigen ad ID, looks like `3071234567`.
+    pub ad_id: String,
+
+    pub url: Url,
+    pub title: String,
+
+    /// Description snippet.
+    pub description: Option<String>,
+
+    pub price: Price,
+    pub delivery: Option<Delivery>,
+
+    /// Postal code and city, like `10115 Berlin`.
+    pub location: Option<String>,
+
+    pub picture_url: Option<Url>,
+}
+
+impl Listing {
+    /// Parse the search result page.
+    pub fn parse_all(html: &str, base_url: &Url) -> Result<Vec<Self>> {
+        Html::parse_document(html)
+            .select(&ARTICLE)
+            .map(|article| Self::parse(article, base_url))
+            .collect()
+    }
+
+    fn parse(article: ElementRef<'_>, base_url: &Url) -> Result<Self> {
+        let ad_id = article.attr("data-adid").context("missing ad ID")?.to_string();
+        let path =
+            article.attr("data-href").with_context(|| format!("missing URL of the ad #{ad_id}"))?;
+        let url = base_url.join(path).context("failed to parse the ad URL")?;
+        let title = article
+            .select(&TITLE)
+            .next()
+            .map(text_of)
+            .with_context(|| format!("missing title of the ad #{ad_id}"))?;
+        let picture_url = article
+            .select(&IMAGE)
+            .next()
+            .and_then(|image| image.attr("data-imgsrc"))
+            .map(Url::parse)
+            .transpose()
+            .context("failed to parse the picture URL")?;
+        Ok(Self {
+            url,
+            title,
+            description: article.select(&DESCRIPTION).next().map(text_of),
+            price: article
+                .select(&PRICE)
+                .next()
+                .map(text_of)
+                .as_deref()
+                .map_or(Ok(Price::SeeDescription), parse_price)?,
+            delivery: article.select(&SHIPPING).next().map(text_of).as_deref().and_then(
+                |shipping| match shipping {
+                    "Versand möglich" => Some(Delivery::Both),
+                    "Nur Abholung" => Some(Delivery::CollectionOnly),
+                    _ => None,
+                },
+            ),
+            location: article.select(&LOCATION).next().map(text_of).filter(|it| !it.is_empty()),
+            picture_url,
+            ad_id,
+        })
+    }
+}
+
+/// Parse the price text, like `1.250 € VB`.
+fn parse_price(text: &str) -> Result<Price> {
+    let is_negotiable = text.ends_with("VB");
+    let amount = text.trim_end_matches("VB").trim();
+    if amount.is_empty() {
+        return Ok(if is_negotiable { Price::ToBeAgreed } else { Price::SeeDescription });
+    }
+    if amount.eq_ignore_ascii_case("zu verschenken") {
+        return Ok(Price::Fixed(Amount::ZERO));
+    }
+    let Some(amount) = amount.strip_suffix('€') else {
+        return Ok(Price::SeeDescription);
+    };
+    let amount = amount
+        .trim()
+        .replace('.', "")
+        .replace(',', ".")
+        .parse::<Decimal>()
+        .with_context(|| format!("failed to parse the price: `{text}`"))?;
+    Ok(if is_negotiable { Price::MinimalBid(Amount(amount)) } else { Price::Fixed(Amount(amount)) })
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn parse_price_ok() -> Result {
+        assert!(
+            matches!(parse_price("1.250 € VB")?, Price::MinimalBid(Amount(amount)) if amount == dec!(1250))
+        );
+        assert!(
+            matches!(parse_price("12,50 €")?, Price::Fixed(Amount(amount)) if amount == dec!(12.50))
+        );
+        assert!(matches!(parse_price("VB")?, Price::ToBeAgreed));
+        assert!(
+            matches!(parse_price("Zu verschenken")?, Price::Fixed(amount) if amount == Amount::ZERO)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_listings_3079412655_ok() -> Result {
+        let base_url = Url::parse("https://www.kleinanzeigen.de")?;
+        // language=html
+        let html = r#"
+            <ul id="srchrslt-adtable" class="itemlist ad-list it3">
+                <li class="ad-listitem fully-clickable-card">
+                    <article class="aditem" data-adid="3079412655" data-href="/s-anzeige/ubiquiti-unifi-u6-pro-access-point-wifi-6/3079412655-225-3487">
+                        <div class="aditem-image">
+                            <a href="/s-anzeige/ubiquiti-unifi-u6-pro-access-point-wifi-6/3079412655-225-3487">
+                                <div class="imagebox srpimagebox" data-imgsrc="https://img.kleinanzeigen.de/api/v1/prod-ads/images/7d/7d2f4e1c-3b9a-4c6e-9f08-5a1d2c7b8e63?rule=$_2.AUTO" data-imgsrcretina="https://img.kleinanzeigen.de/api/v1/prod-ads/images/7d/7d2f4e1c-3b9a-4c6e-9f08-5a1d2c7b8e63?rule=$_35.AUTO 2x" data-imgtitle="Ubiquiti UniFi U6 Pro Access Point WiFi 6 Berlin - Mitte Vorschau">
+                                    <img src="https://img.kleinanzeigen.de/api/v1/prod-ads/images/7d/7d2f4e1c-3b9a-4c6e-9f08-5a1d2c7b8e63?rule=$_2.AUTO" alt="Ubiquiti UniFi U6 Pro Access Point WiFi 6 Berlin - Mitte Vorschau" loading="lazy">
+                                    <div class="galleryimage--counter">3</div>
+                                </div>
+                            </a>
+                        </div>
+                        <div class="aditem-main">
+                            <div class="aditem-main--top">
+                                <div class="aditem-main--top--left">
+                                    <i class="icon icon-small icon-pin-gray"></i> 10119 Mitte
+                                </div>
+                                <div class="aditem-main--top--right">
+                                    <i class="icon icon-small icon-calendar-open"></i>
+                                    Heute, 09:41</div>
+                            </div>
+                            <div class="aditem-main--middle">
+                                <h2 class="text-module-begin">
+                                    <a class="ellipsis" href="/s-anzeige/ubiquiti-unifi-u6-pro-access-point-wifi-6/3079412655-225-3487">Ubiquiti UniFi U6 Pro Access Point WiFi 6</a>
+                                </h2>
+                                <p class="aditem-main--middle--description">Verkaufe meinen U6 Pro, lief ein Jahr an der Decke im Büro. Inklusive Halterung, ohne PoE-Injektor.…</p>
+                                <div class="aditem-main--middle--price-shipping">
+                                    <p class="aditem-main--middle--price-shipping--price">
+                                        110 € VB</p>
+                                    <p class="aditem-main--middle--price-shipping--shipping">
+                                        Versand möglich</p>
+                                </div>
+                            </div>
+                            <div class="aditem-main--bottom">
+                                <p class="text-module-end"></p>
+                            </div>
+                        </div>
+                    </article>
+                </li>
+                <li class="ad-listitem fully-clickable-card">
+                    <article class="aditem" data-adid="3079398120" data-href="/s-anzeige/unifi-switch-lite-8-poe-usw-lite-8-poe/3079398120-225-1931">
+                        <div class="aditem-image">
+                            <a href="/s-anzeige/unifi-switch-lite-8-poe-usw-lite-8-poe/3079398120-225-1931">
+                                <div class="imagebox srpimagebox">
+                                    <div class="imagebox-new-placeholder"></div>
+                                </div>
+                            </a>
+                        </div>
+                        <div class="aditem-main">
+                            <div class="aditem-main--top">
+                                <div class="aditem-main--top--left">
+                                </div>
+                                <div class="aditem-main--top--right">
+                                    <i class="icon icon-small icon-calendar-open"></i>
+                                    Gestern, 21:17</div>
+                            </div>
+                            <div class="aditem-main--middle">
+                                <h2 class="text-module-begin">
+                                    <a class="ellipsis" href="/s-anzeige/unifi-switch-lite-8-poe-usw-lite-8-poe/3079398120-225-1931">UniFi Switch Lite 8 PoE (USW-Lite-8-PoE)</a>
+                                </h2>
+                                <p class="aditem-main--middle--description">Switch wird nicht mehr gebraucht, bitte nur Abholung.</p>
+                                <div class="aditem-main--middle--price-shipping">
+                                    <p class="aditem-main--middle--price-shipping--price">
+                                        Zu verschenken</p>
+                                    <p class="aditem-main--middle--price-shipping--shipping">
+                                        Nur Abholung</p>
+                                </div>
+                            </div>
+                        </div>
+                    </article>
+                </li>
+            </ul>
+        "#;
+        let listings = Listing::parse_all(html, &base_url)?;
+        assert_eq!(listings.len(), 2);
+
+        let listing = &listings[0];
+        assert_eq!(listing.ad_id, "3079412655");
+        assert_eq!(
+            listing.url.as_str(),
+            "https://www.kleinanzeigen.de/s-anzeige/ubiquiti-unifi-u6-pro-access-point-wifi-6/3079412655-225-3487",
+        );
+        assert_eq!(listing.title, "Ubiquiti UniFi U6 Pro Access Point WiFi 6");
+        assert_eq!(listing.location.as_deref(), Some("10119 Mitte"));
+        assert!(matches!(listing.price, Price::MinimalBid(Amount(amount)) if amount == dec!(110)));
+        assert_eq!(listing.delivery, Some(Delivery::Both));
+        assert_eq!(
+            listing.picture_url.as_ref().map(Url::as_str),
+            Some(
+                "https://img.kleinanzeigen.de/api/v1/prod-ads/images/7d/7d2f4e1c-3b9a-4c6e-9f08-5a1d2c7b8e63?rule=$_2.AUTO"
+            ),
+        );
+
+        let listing = &listings[1];
+        assert_eq!(listing.ad_id, "3079398120");
+        assert!(matches!(listing.price, Price::Fixed(amount) if amount == Amount::ZERO));
+        assert_eq!(listing.delivery, Some(Delivery::CollectionOnly));
+        assert_eq!(listing.location, None);
+        assert_eq!(listing.picture_url, None);
+
+        Ok(())
+    }
+}
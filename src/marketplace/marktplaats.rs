@@ -1,15 +1,24 @@
 mod client;
+mod item_page;
 mod listing;
+mod site;
 
 use async_trait::async_trait;
 use bon::Builder;
+use rust_decimal::Decimal;
 
-use self::client::SearchRequest;
-pub use self::{client::MarktplaatsClient, listing::Listings};
+pub use self::{client::MarktplaatsClient, listing::Listings, site::Site};
+use self::{client::SearchRequest, item_page::Config};
 use crate::{
     db::SearchQuery,
     heartbeat::Heartbeat,
-    marketplace::{Marketplace, item::Item},
+    marketplace::{
+        Dropped,
+        Marketplace,
+        NormalisedQuery,
+        SearchOutcome,
+        item::{Details, Item},
+    },
     prelude::*,
 };
 
@@ -24,16 +33,33 @@ pub struct Marktplaats {
 
 #[async_trait]
 impl Marketplace for Marktplaats {
+    fn id(&self) -> &'static str {
+        self.client.site().id()
+    }
+
+    fn name(&self) -> &'static str {
+        self.client.site().name()
+    }
+
     async fn check_in(&self) {
         self.heartbeat.check_in().await;
     }
 
-    /// Search Marktplaats.
-    async fn search(&mut self, query: &SearchQuery) -> Result<Vec<Item>> {
+    /// Search the Marktplaats platform site.
+    async fn search_explained(&self, query: &SearchQuery) -> Result<SearchOutcome> {
+        let site = self.client.site();
         let query = query.normalised_query();
         let search_text = query.search_text();
+        let seller_ids = query
+            .seller()
+            .map(str::parse::<u32>)
+            .transpose()
+            .context("Marktplaats seller ID must be a number")?;
+        let attribute_ranges = price_range(&query)?;
         let listings = SearchRequest::builder()
-            .query(&search_text)
+            .maybe_query(Some(search_text.as_str()).filter(|text| !text.is_empty()))
+            .seller_ids(seller_ids.as_slice())
+            .attribute_ranges(attribute_ranges.as_slice())
             .limit(self.search_limit)
             .search_in_title_and_description(self.search_in_title_and_description)
             .build()
@@ -41,15 +67,55 @@ impl Marketplace for Marktplaats {
             .await?
             .inner;
         let n_fetched = listings.len();
-        let items = listings
-            .into_iter()
-            .filter(|listing| {
-                query.matches(listing.title.split_whitespace().chain(listing.brand().into_iter()))
-            })
-            .map(TryInto::<Item>::try_into)
-            .collect::<Result<Vec<Item>>>()?;
-        info!(search_text, n_fetched, n_filtered = items.len(), "🛍️ Fetched");
-        self.check_in().await;
-        Ok(items)
+        let mut outcome = SearchOutcome::default();
+        for listing in listings {
+            let mismatch =
+                query.mismatch(listing.title.split_whitespace().chain(listing.brand().into_iter()));
+            let item = listing.into_item(site)?;
+            match mismatch {
+                None => outcome.items.push(item),
+                Some(reason) => outcome.dropped.push(Dropped::new(item, reason)),
+            }
+        }
+        info!(?site, search_text, n_fetched, n_filtered = outcome.items.len(), "🛍️ Fetched");
+        Ok(outcome)
+    }
+
+    async fn fetch_details(&self, item: &Item) -> Result<Option<Details>> {
+        let html = self.client.fetch_page(item.url.clone()).await?;
+        Ok(Some(Config::parse(&html)?.into()))
+    }
+}
+
+/// Convert the `price:` modifier into the price attribute range.
+fn price_range(query: &NormalisedQuery) -> Result<Vec<String>> {
+    let bound = |price: Option<Decimal>| {
+        price.map_or_else(
+            || "null".to_string(),
+            |price| (price * Decimal::ONE_HUNDRED).round().to_string(),
+        )
+    };
+    Ok(match query.price_range()? {
+        (None, None) => vec![],
+        (from, to) => vec![format!("PriceCents:{}:{}", bound(from), bound(to))],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_range_ok() -> Result {
+        assert!(price_range(&NormalisedQuery::parse("jacket"))?.is_empty());
+        assert_eq!(
+            price_range(&NormalisedQuery::parse("jacket price:10-50.5"))?,
+            ["PriceCents:1000:5050"],
+        );
+        assert_eq!(
+            price_range(&NormalisedQuery::parse("jacket price:50"))?,
+            ["PriceCents:null:5000"]
+        );
+        Ok(())
     }
 }
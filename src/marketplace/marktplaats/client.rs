@@ -3,15 +3,30 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::Serialize;
 use url::Url;
 
-use crate::{marketplace::marktplaats::Listings, prelude::*};
+use crate::{
+    marketplace::marktplaats::{Listings, Site},
+    prelude::*,
+};
 
+/// Client for the Marktplaats platform API, bound to one of its sites.
 #[must_use]
 #[derive(Clone)]
-pub struct MarktplaatsClient(pub ClientWithMiddleware);
+pub struct MarktplaatsClient {
+    client: ClientWithMiddleware,
+    site: Site,
+}
 
 impl MarktplaatsClient {
-    /// Search Marktplaats.
-    #[instrument(skip_all)]
+    pub const fn new(client: ClientWithMiddleware, site: Site) -> Self {
+        Self { client, site }
+    }
+
+    pub const fn site(&self) -> Site {
+        self.site
+    }
+
+    /// Search the site.
+    #[instrument(skip_all, fields(site = ?self.site))]
     pub async fn search(&self, request: &SearchRequest<'_>) -> Result<Listings> {
         info!(
             query = request.query,
@@ -22,11 +37,32 @@ impl MarktplaatsClient {
         let url = {
             let query =
                 serde_qs::to_string(request).context("failed to serialize the search request")?;
-            let mut url = Url::parse("https://www.marktplaats.nl/lrp/api/search")?;
+            let mut url = self.site.url("/lrp/api/search")?;
             url.set_query(Some(&query));
             url
         };
-        self.0.get(url).send().await?.error_for_status()?.json().await.context("failed to search")
+        self.client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("failed to search")
+    }
+
+    /// Fetch the HTML page, for example, an item page.
+    #[instrument(skip_all, fields(site = ?self.site, url = %url))]
+    pub async fn fetch_page(&self, url: Url) -> Result<String> {
+        info!("🔎 Fetching the page…");
+        self.client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+            .context("failed to fetch the page")
     }
 }
 
@@ -56,6 +92,11 @@ pub struct SearchRequest<'a> {
     #[serde(rename = "sellerIds")]
     #[builder(default)]
     pub seller_ids: &'a [u32],
+
+    /// Attribute ranges, for example, `PriceCents:1000:5000`.
+    #[serde(rename = "attributeRanges")]
+    #[builder(default)]
+    pub attribute_ranges: &'a [String],
 }
 
 impl SearchRequest<'_> {
@@ -110,4 +151,15 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn search_request_with_attribute_ranges_ok() -> Result {
+        let attribute_ranges = ["PriceCents:1000:null".to_string()];
+        let request = SearchRequest::builder().attribute_ranges(&attribute_ranges).build();
+        assert_eq!(
+            serde_qs::to_string(&request)?,
+            "sortBy=SORT_INDEX&sortOrder=DECREASING&attributeRanges[0]=PriceCents%3A1000%3Anull",
+        );
+        Ok(())
+    }
 }
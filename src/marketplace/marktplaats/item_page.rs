@@ -0,0 +1,96 @@
+//! Item page parsing.
+//!
+//! The search results truncate the description and lack the seller rating, but the item page
+//! embeds the full listing as `window.__CONFIG__`.
+
+use serde::Deserialize;
+
+use crate::{
+    marketplace::item::{Details, Rating},
+    prelude::*,
+};
+
+const CONFIG_PREFIX: &str = "window.__CONFIG__ = ";
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub listing: Listing,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Listing {
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub seller: Option<Seller>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Seller {
+    #[serde(rename = "averageScore", default)]
+    pub average_score: Option<f64>,
+
+    #[serde(rename = "numberOfReviews", default)]
+    pub n_reviews: u32,
+}
+
+impl Config {
+    /// Extract the config from the item page.
+    pub fn parse(html: &str) -> Result<Self> {
+        let start =
+            html.find(CONFIG_PREFIX).context("missing item page config")? + CONFIG_PREFIX.len();
+        let config = html[start..].split_once(";\n").map_or(&html[start..], |(config, _)| config);
+        serde_json::from_str(config.trim()).context("failed to deserialize the item page config")
+    }
+}
+
+impl From<Config> for Details {
+    fn from(config: Config) -> Self {
+        let seller_rating = config.listing.seller.and_then(|seller| {
+            seller
+                .average_score
+                .filter(|_| seller.n_reviews != 0)
+                .map(|score| Rating { score, n_reviews: seller.n_reviews })
+        });
+        Self {
+            description: config.listing.description.filter(|description| !description.is_empty()),
+            seller_rating,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config_ok() -> Result {
+        // language=html
+        let html = r#"
+            <html>
+            <body>
+                <script>
+                    window.__CONFIG__ = {"listing":{"itemId":"m2137081815","description":"Volledige beschrijving; met alle details.","seller":{"id":12345,"name":"Jan","averageScore":4.7,"numberOfReviews":23}},"tenantContext":{"tenant":"mp"}};
+                    window.__ENV__ = {};
+                </script>
+            </body>
+            </html>
+        "#;
+        let details = Details::from(Config::parse(html)?);
+        assert_eq!(
+            details.description.as_deref(),
+            Some("Volledige beschrijving; met alle details.")
+        );
+        let rating = details.seller_rating.unwrap();
+        assert!((rating.score - 4.7).abs() < f64::EPSILON);
+        assert_eq!(rating.n_reviews, 23);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_missing_config_fails() {
+        assert!(Config::parse("<html></html>").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use url::Url;
+
+use crate::prelude::*;
+
+/// Website running on the Marktplaats platform.
+///
+/// The websites share the same API and response shape, but have their own listings.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Site {
+    Marktplaats,
+
+    /// Belgian 2dehands.be, Dutch-speaking.
+    TweedeHands,
+
+    /// Belgian 2ememain.be, French-speaking: the same listings as on 2dehands.be.
+    DeuxiemeMain,
+}
+
+impl Site {
+    /// Stable marketplace ID.
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Marktplaats => "marktplaats",
+            Self::TweedeHands | Self::DeuxiemeMain => "2dehands",
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Marktplaats => "Marktplaats",
+            Self::TweedeHands => "2dehands",
+            Self::DeuxiemeMain => "2ememain",
+        }
+    }
+
+    pub const fn base_url(self) -> &'static str {
+        match self {
+            Self::Marktplaats => "https://www.marktplaats.nl",
+            Self::TweedeHands => "https://www.2dehands.be",
+            Self::DeuxiemeMain => "https://www.2ememain.be",
+        }
+    }
+
+    /// Prefix of the item IDs, which prevents clashes between the sites.
+    ///
+    /// Marktplaats has no prefix for backwards compatibility.
+    pub const fn item_id_prefix(self) -> &'static str {
+        match self {
+            Self::Marktplaats => "",
+            Self::TweedeHands | Self::DeuxiemeMain => "2dehands::",
+        }
+    }
+
+    /// Build the absolute URL from the path.
+    pub fn url(self, path: &str) -> Result<Url> {
+        Url::parse(&format!("{}{path}", self.base_url()))
+            .with_context(|| format!("failed to build the URL for `{path}`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_ok() -> Result {
+        assert_eq!(
+            Site::TweedeHands.url("/lrp/api/search")?.as_str(),
+            "https://www.2dehands.be/lrp/api/search",
+        );
+        Ok(())
+    }
+}
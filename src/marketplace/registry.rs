@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use crate::{
+    db::{Db, ItemDetails, SearchQuery},
+    marketplace::{
+        Marketplace,
+        item::{Details, Item},
+    },
+    prelude::*,
+};
+
+/// Registry of the marketplaces, shared between the bots.
+#[must_use]
+#[derive(Clone, Default)]
+pub struct Marketplaces(Arc<Vec<Registration>>);
+
+struct Registration {
+    marketplace: Box<dyn Marketplace>,
+    is_enabled: bool,
+}
+
+impl Marketplaces {
+    /// Register the marketplace.
+    ///
+    /// Disabled marketplaces are known to the registry, but never searched on.
+    ///
+    /// # Panics
+    ///
+    /// Panics on a duplicate ID, or when the registry is already shared.
+    pub fn register(mut self, marketplace: impl Marketplace + 'static, is_enabled: bool) -> Self {
+        let registrations = Arc::get_mut(&mut self.0).expect("the registry is already shared");
+        assert!(
+            registrations
+                .iter()
+                .all(|registration| registration.marketplace.id() != marketplace.id()),
+            "duplicate marketplace ID: `{}`",
+            marketplace.id(),
+        );
+        info!(id = marketplace.id(), is_enabled, "🛒 Registered marketplace");
+        registrations.push(Registration { marketplace: Box::new(marketplace), is_enabled });
+        self
+    }
+
+    /// Iterate over the enabled marketplaces.
+    pub fn enabled(&self) -> impl Iterator<Item = &dyn Marketplace> {
+        self.0
+            .iter()
+            .filter(|registration| registration.is_enabled)
+            .map(|registration| registration.marketplace.as_ref())
+    }
+
+    /// Get the registered marketplace, enabled or not.
+    pub fn get(&self, id: &str) -> Option<&dyn Marketplace> {
+        self.0
+            .iter()
+            .map(|registration| registration.marketplace.as_ref())
+            .find(|marketplace| marketplace.id() == id)
+    }
+
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.enabled().any(|marketplace| marketplace.id() == id)
+    }
+
+    /// Search on the enabled marketplaces, which the query allows, logging the errors.
+    ///
+    /// # Arguments
+    ///
+    /// - `limit`: limit of the items per marketplace
+    pub async fn search(&self, query: &SearchQuery, limit: Option<usize>) -> Vec<Item> {
+        let normalised_query = query.normalised_query();
+        let mut items = Vec::new();
+        for marketplace in self
+            .enabled()
+            .filter(|marketplace| normalised_query.allows_marketplace(marketplace.id()))
+        {
+            marketplace.search_and_extend_infallible(query, limit, &mut items).await;
+        }
+        items
+    }
+
+    /// Fill in the item with its details, fetching them at most once per item.
+    ///
+    /// Failures are logged, and the item is returned as is,
+    /// unless its marketplace [requires the details][Marketplace::requires_details].
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the required details could not be fetched.
+    pub async fn enrich(&self, db: &Db, mut item: Item) -> Option<Item> {
+        match self.fetch_details(db, &item).await {
+            Ok(Some(details)) => item.apply(details),
+            Ok(None) => {}
+            Err(error) => {
+                warn!(item.id, "⚠️ Failed to fetch the item details: {error:#}");
+                if self.get(item.marketplace_id).is_some_and(Marketplace::requires_details) {
+                    return None;
+                }
+            }
+        }
+        Some(item)
+    }
+
+    async fn fetch_details(&self, db: &Db, item: &Item) -> Result<Option<Details>> {
+        if let Some(details) = ItemDetails(&mut *db.connection().await?).fetch(&item.id).await? {
+            return Ok(Some(details));
+        }
+        let Some(marketplace) = self.get(item.marketplace_id) else {
+            return Ok(None);
+        };
+        let details = marketplace.fetch_details(item).await?;
+        if let Some(details) = &details {
+            info!(item.id, "🔎 Fetched the item details");
+            ItemDetails(&mut *db.connection().await?).upsert(&item.id, details).await?;
+        }
+        Ok(details)
+    }
+
+    /// Check in all the enabled marketplaces' heartbeats.
+    pub async fn check_in(&self) {
+        for marketplace in self.enabled() {
+            marketplace.check_in().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::marketplace::SearchOutcome;
+
+    struct Dummy(&'static str);
+
+    #[async_trait]
+    impl Marketplace for Dummy {
+        fn id(&self) -> &'static str {
+            self.0
+        }
+
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn check_in(&self) {}
+
+        async fn search_explained(&self, _query: &SearchQuery) -> Result<SearchOutcome> {
+            Ok(SearchOutcome::default())
+        }
+    }
+
+    #[test]
+    fn enabled_ok() {
+        let marketplaces = Marketplaces::default()
+            .register(Dummy("enabled"), true)
+            .register(Dummy("disabled"), false);
+        assert_eq!(marketplaces.enabled().map(Marketplace::id).collect::<Vec<_>>(), ["enabled"],);
+    }
+
+    #[test]
+    #[should_panic = "duplicate marketplace ID"]
+    fn duplicate_id_panics() {
+        let _ =
+            Marketplaces::default().register(Dummy("dummy"), true).register(Dummy("dummy"), false);
+    }
+}
@@ -1,21 +1,67 @@
-use std::{borrow::Cow, collections::BTreeSet};
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
 
 use itertools::Itertools;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::prelude::*;
+
+/// Keys of the recognised `key:value` modifiers.
+///
+/// Other tokens with a colon are plain search terms.
+const MODIFIER_KEYS: &[&str] = &[
+    "site", "seller", // Vinted catalog filters:
+    "brand", "size", "catalog", "color", "status", "price",
+];
+
+/// Modifiers, which only some marketplaces support, along with the IDs of those marketplaces.
+///
+/// Such a modifier restricts the query to the marketplaces, which support it,
+/// instead of being silently ignored by the others.
+const RESTRICTING_MODIFIERS: &[(&str, &[&str])] = &[
+    ("brand", &["vinted"]),
+    ("size", &["vinted"]),
+    ("catalog", &["vinted"]),
+    ("color", &["vinted"]),
+    ("status", &["vinted"]),
+    ("price", &["vinted", "marktplaats", "2dehands"]),
+];
 
 #[derive(Clone, Debug)]
 pub struct NormalisedQuery {
     include: BTreeSet<String>,
     exclude: BTreeSet<String>,
+
+    /// `key:value` modifiers, which are not a part of the search text.
+    modifiers: BTreeSet<(String, String)>,
 }
 
 impl NormalisedQuery {
     pub fn parse(text: &str) -> Self {
-        let mut this = Self { include: BTreeSet::new(), exclude: BTreeSet::new() };
-        for token in text.split_whitespace().map(str::to_lowercase).sorted() {
-            if let Some(token) = token.strip_prefix('-') {
+        let mut this =
+            Self { include: BTreeSet::new(), exclude: BTreeSet::new(), modifiers: BTreeSet::new() };
+        for token in tokenize(&text.to_lowercase()).sorted() {
+            if let Some((key, values)) =
+                token.split_once(':').filter(|(key, _)| MODIFIER_KEYS.contains(key))
+            {
+                for value in values
+                    .split(',')
+                    .map(|value| {
+                        value.trim_matches(|char: char| char == '"' || char.is_whitespace())
+                    })
+                    .filter(|value| !value.is_empty())
+                {
+                    this.modifiers.insert((key.to_string(), value.to_string()));
+                }
+            } else if let Some(token) = token.strip_prefix('-') {
                 this.exclude.insert(token.to_string());
             } else {
-                this.include.insert(token);
+                this.include.insert(token.to_string());
             }
         }
         this
@@ -27,13 +73,125 @@ impl NormalisedQuery {
 
     pub fn unparse(&self) -> String {
         let positive = self.include.iter().map(Cow::Borrowed);
+        let modifiers = self.modifiers.iter().map(|(key, value)| {
+            Cow::<String>::Owned(if value.contains(char::is_whitespace) {
+                format!("{key}:\"{value}\"")
+            } else {
+                format!("{key}:{value}")
+            })
+        });
         let negative = self.exclude.iter().map(|token| Cow::<String>::Owned(format!("-{token}")));
-        positive.chain(negative).join(" ")
+        positive.chain(modifiers).chain(negative).join(" ")
+    }
+
+    /// Iterate over the values of the modifier.
+    pub fn modifier<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.modifiers
+            .iter()
+            .filter(move |(modifier_key, _)| modifier_key == key)
+            .map(|(_, value)| value.as_str())
     }
 
-    pub fn matches<'a>(&self, terms: impl IntoIterator<Item = &'a str>) -> bool {
+    /// Check whether the query should run on the marketplace.
+    ///
+    /// Without `site:` modifiers, the query runs on all the marketplaces,
+    /// which support its other modifiers.
+    pub fn allows_marketplace(&self, id: &str) -> bool {
+        let mut sites = self.modifier("site").peekable();
+        (sites.peek().is_none() || sites.any(|site| site == id))
+            && RESTRICTING_MODIFIERS
+                .iter()
+                .all(|(key, ids)| self.modifier(key).next().is_none() || ids.contains(&id))
+    }
+
+    /// Price range from the `price:from-to` modifier, in euros.
+    ///
+    /// `price:50` and `price:-50` set the upper bound only, `price:10-` – the lower bound only.
+    pub fn price_range(&self) -> Result<(Option<Decimal>, Option<Decimal>)> {
+        let Some(value) = self.modifier("price").last() else {
+            return Ok((None, None));
+        };
+        let (from, to) = value.split_once('-').unwrap_or(("", value));
+        Ok((parse_price(from)?, parse_price(to)?))
+    }
+
+    /// Seller ID to follow instead of searching by the text.
+    pub fn seller(&self) -> Option<&str> {
+        self.modifier("seller").next()
+    }
+
+    /// Explain why the terms do not match the query.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the terms match.
+    pub fn mismatch<'a>(&self, terms: impl IntoIterator<Item = &'a str>) -> Option<Mismatch> {
         let terms: BTreeSet<_> = terms.into_iter().map(str::to_lowercase).collect();
-        self.include.is_subset(&terms) && self.exclude.is_disjoint(&terms)
+        let missing = self.include.difference(&terms).cloned().collect_vec();
+        if !missing.is_empty() {
+            return Some(Mismatch::Missing(missing));
+        }
+        let excluded = self.exclude.intersection(&terms).cloned().collect_vec();
+        if !excluded.is_empty() {
+            return Some(Mismatch::Excluded(excluded));
+        }
+        None
+    }
+}
+
+/// Split the text into whitespace-separated tokens.
+///
+/// A modifier value may be quoted to include spaces, for example: `brand:"the north face"`.
+fn tokenize(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text.trim_start();
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if let Some((key, value)) = rest[..end].split_once(':') {
+            let value_start = key.len() + 2;
+            if MODIFIER_KEYS.contains(&key) && value.starts_with('"') {
+                // Extend the token past the closing quote, or to the end of the text if it is missing:
+                end = rest[value_start..].find('"').map_or(rest.len(), |i| {
+                    let quote_end = value_start + i + 1;
+                    rest[quote_end..]
+                        .find(char::is_whitespace)
+                        .map_or(rest.len(), |j| quote_end + j)
+                });
+            }
+        }
+        let (token, tail) = rest.split_at(end);
+        rest = tail.trim_start();
+        Some(token)
+    })
+}
+
+fn parse_price(value: &str) -> Result<Option<Decimal>> {
+    if value.is_empty() {
+        Ok(None)
+    } else {
+        Decimal::from_str(value).map(Some).with_context(|| format!("invalid price: `{value}`"))
+    }
+}
+
+/// Reason why the query post-filter dropped a listing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mismatch {
+    /// The listing lacks these search terms.
+    Missing(Vec<String>),
+
+    /// The listing contains these excluded terms.
+    Excluded(Vec<String>),
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(terms) => write!(f, "missing {}", terms.join(", ")),
+            Self::Excluded(terms) => write!(f, "excluded {}", terms.join(", ")),
+        }
     }
 }
 
@@ -48,6 +206,75 @@ mod tests {
         assert_eq!(query.exclude.iter().collect_vec(), &["samsung"]);
     }
 
+    #[test]
+    fn parse_modifiers_ok() {
+        let query = NormalisedQuery::parse("jacket site:vinted,Kleinanzeigen -kids 12:30");
+        assert_eq!(query.search_text(), "12:30 jacket");
+        assert_eq!(query.modifier("site").collect_vec(), &["kleinanzeigen", "vinted"]);
+        assert_eq!(query.unparse(), "12:30 jacket site:kleinanzeigen site:vinted -kids");
+        assert!(query.allows_marketplace("vinted"));
+        assert!(!query.allows_marketplace("marktplaats"));
+    }
+
+    #[test]
+    fn parse_quoted_modifiers_ok() {
+        let query = NormalisedQuery::parse(r#"jacket brand:"The North Face",patagonia  size:"m""#);
+        assert_eq!(query.search_text(), "jacket");
+        assert_eq!(query.modifier("brand").collect_vec(), &["patagonia", "the north face"]);
+        assert_eq!(query.modifier("size").collect_vec(), &["m"]);
+        assert_eq!(query.unparse(), r#"jacket brand:patagonia brand:"the north face" size:m"#);
+        assert_eq!(NormalisedQuery::parse(&query.unparse()).unparse(), query.unparse());
+    }
+
+    #[test]
+    fn parse_unclosed_quote_ok() {
+        let query = NormalisedQuery::parse(r#"jacket brand:"the north face"#);
+        assert_eq!(query.search_text(), "jacket");
+        assert_eq!(query.modifier("brand").collect_vec(), &["the north face"]);
+    }
+
+    #[test]
+    fn seller_ok() {
+        let query = NormalisedQuery::parse("site:marktplaats seller:23640587");
+        assert_eq!(query.seller(), Some("23640587"));
+        assert_eq!(query.search_text(), "");
+        assert!(query.mismatch("Ubiquiti UniFi Cloud Gateway Ultra".split_whitespace()).is_none());
+    }
+
+    #[test]
+    fn allows_any_marketplace_ok() {
+        assert!(NormalisedQuery::parse("unifi").allows_marketplace("marktplaats"));
+    }
+
+    #[test]
+    fn restricting_modifiers_ok() {
+        let query = NormalisedQuery::parse("jacket size:42");
+        assert_eq!(query.search_text(), "jacket");
+        assert!(query.allows_marketplace("vinted"));
+        assert!(!query.allows_marketplace("marktplaats"));
+
+        let query = NormalisedQuery::parse("jacket price:10-50");
+        assert!(query.allows_marketplace("marktplaats"));
+        assert!(!query.allows_marketplace("kleinanzeigen"));
+
+        let query = NormalisedQuery::parse("jacket brand:nike site:marktplaats");
+        assert!(!query.allows_marketplace("marktplaats"));
+        assert!(!query.allows_marketplace("vinted"));
+    }
+
+    #[test]
+    fn price_range_ok() -> Result {
+        assert_eq!(NormalisedQuery::parse("jacket").price_range()?, (None, None));
+        assert_eq!(
+            NormalisedQuery::parse("jacket price:10-50.5").price_range()?,
+            (Some(Decimal::from(10)), Some(Decimal::new(505, 1))),
+        );
+        assert_eq!(NormalisedQuery::parse("price:50").price_range()?, (None, Some(50.into())));
+        assert_eq!(NormalisedQuery::parse("price:10-").price_range()?, (Some(10.into()), None));
+        assert!(NormalisedQuery::parse("price:abc").price_range().is_err());
+        Ok(())
+    }
+
     #[test]
     fn unparse_ok() {
         let query = NormalisedQuery::parse("-samsung smartphone");
@@ -64,16 +291,30 @@ mod tests {
     fn matches_ok() {
         let query = NormalisedQuery::parse("-samsung foldable smartphone");
         assert!(
-            query.matches("Xiaomi Foldable Smartphone".split_whitespace()),
+            query.mismatch("Xiaomi Foldable Smartphone".split_whitespace()).is_none(),
             "contains all the positives and no negatives"
         );
         assert!(
-            !query.matches("Samsung Foldable Smartphone".split_whitespace()),
+            query.mismatch("Samsung Foldable Smartphone".split_whitespace()).is_some(),
             "contains all the positives but also the negative"
         );
         assert!(
-            !query.matches("xiaomi smartphone".split_whitespace()),
+            query.mismatch("xiaomi smartphone".split_whitespace()).is_some(),
             "does not contain all the positives"
         );
     }
+
+    #[test]
+    fn mismatch_ok() {
+        let query = NormalisedQuery::parse("-samsung -case foldable smartphone");
+        assert_eq!(
+            query.mismatch("Xiaomi Smartphone".split_whitespace()),
+            Some(Mismatch::Missing(vec!["foldable".to_string()])),
+        );
+        assert_eq!(
+            query.mismatch("Samsung Foldable Smartphone Case".split_whitespace()),
+            Some(Mismatch::Excluded(vec!["case".to_string(), "samsung".to_string()])),
+        );
+        assert_eq!(query.mismatch("Xiaomi Foldable Smartphone".split_whitespace()), None);
+    }
 }
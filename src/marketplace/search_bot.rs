@@ -1,22 +1,39 @@
-use std::{borrow::Cow, time::Duration};
-
 use bon::Builder;
 use chrono::Utc;
-use tokio::time::sleep;
+use reqwest_middleware::ClientWithMiddleware;
+use tokio::{sync::watch, time::sleep};
 use tracing::{error, info};
 
 use crate::{
+    config::Reloadable,
     db,
-    db::{Db, Item, Items, Notifications, SearchQuery, Subscription},
-    marketplace::{Marketplace, marktplaats::Marktplaats, vinted::Vinted},
+    db::{
+        BlockedSeller,
+        BlockedSellers,
+        Chats,
+        Db,
+        Item,
+        ItemFingerprints,
+        Items,
+        Notifications,
+        Outbox,
+        OutboxEntry,
+        SearchQueries,
+        SearchQuery,
+        Subscription,
+    },
+    marketplace,
+    marketplace::{
+        Marketplaces,
+        item::{Fingerprint, fingerprint},
+    },
     prelude::{instrument, *},
-    telegram,
+    quotas::Usage,
     telegram::{
-        Telegram,
         commands::CommandBuilder,
-        objects::ParseMode,
+        notification::Notification,
         render,
-        render::ManageSearchQuery,
+        render::{CommandLink, ManageSearchQuery},
     },
 };
 
@@ -27,26 +44,24 @@ pub struct SearchBot {
 
     command_builder: CommandBuilder, // TODO: should it belong in `Telegram`?
 
-    /// Search interval between subscriptions.
-    search_interval: Duration,
+    /// Search interval, duplicate window, and quotas.
+    reloadable: watch::Receiver<Reloadable>,
 
-    /// Telegram connection.
-    telegram: Telegram,
+    /// HTTP client to fetch the item pictures.
+    client: ClientWithMiddleware,
 
-    /// Marktplaats connection.
-    marktplaats: Marktplaats,
-
-    /// Vinted connection.
-    vinted: Vinted,
+    /// Marketplaces to search on.
+    marketplaces: Marketplaces,
 }
 
 impl SearchBot {
     /// Run the bot indefinitely.
     pub async fn run(mut self) {
-        info!(?self.search_interval, "🔄 Running the search bot…");
+        info!("🔄 Running the search bot…");
         let mut previous = None;
         loop {
-            sleep(self.search_interval).await;
+            let search_interval = self.reloadable.borrow().search_interval;
+            sleep(search_interval).await;
             match self.advance_and_handle(previous.as_ref()).await {
                 Ok(handled) => {
                     previous = handled;
@@ -79,8 +94,7 @@ impl SearchBot {
             Ok(current)
         } else {
             info!("📭 No active subscriptions");
-            self.marktplaats.check_in().await;
-            self.vinted.check_in().await;
+            self.marketplaces.check_in().await;
             Ok(None)
         }
     }
@@ -93,39 +107,203 @@ impl SearchBot {
         search_query: &SearchQuery,
     ) -> Result {
         info!(subscription.chat_id, search_query.text, "🏭 Handling…");
-        let unsubscribe_link = self.command_builder.unsubscribe_link(search_query.hash);
+        let unsubscribe_link =
+            self.command_builder.for_chat(subscription.chat_id).unsubscribe_link(search_query.hash);
 
-        let mut items = Vec::new();
-        self.marktplaats.search_and_extend_infallible(search_query, None, &mut items).await;
-        self.vinted.search_and_extend_infallible(search_query, None, &mut items).await;
+        let mut items = self.marketplaces.search(search_query, None).await;
 
         info!(n_items = items.len(), "🛍️ Fetched from all marketplaces");
+        let (chat, blocked_sellers, notified, mut usage) = {
+            let connection = &mut *self.db.connection().await?;
+            let since = Utc::now() - self.reloadable.borrow().duplicate_window;
+            (
+                Chats(connection).fetch(subscription.chat_id).await?,
+                BlockedSellers(connection).fetch_all_of(subscription.chat_id).await?,
+                ItemFingerprints(connection)
+                    .fetch_notified_since(subscription.chat_id, since)
+                    .await?,
+                Usage::fetch(connection, subscription.chat_id).await?,
+            )
+        };
+        items.retain(|item| !blocked_sellers.iter().any(|seller| seller.blocks(item)));
+        let mut new_items = Vec::new();
         for item in items {
-            let mut connection = self.db.connection().await;
-            Items(&mut connection).upsert(Item { id: &item.id, updated_at: Utc::now() }).await?;
+            let connection = &mut *self.db.connection().await?;
+            Items(connection).upsert(&Item::seen(&item, Utc::now())).await?;
             let notification =
                 db::Notification { item_id: item.id.clone(), chat_id: subscription.chat_id };
-            if Notifications(&mut connection).exists(&notification).await? {
+            if Notifications(connection).exists(&notification).await? {
                 trace!(subscription.chat_id, item.id, "✅ Notification was already sent");
-                continue;
+            } else {
+                new_items.push(item);
+            }
+        }
+        let remaining = self.reloadable.borrow().quotas.remaining_notifications(usage);
+        for group in
+            self.group_duplicates(new_items, &blocked_sellers, &notified, remaining).await?
+        {
+            if !self.reloadable.borrow().quotas.allows_notification(usage) {
+                info!(subscription.chat_id, "🔕 Notification quota is exhausted");
+                break;
             }
-            info!(subscription.chat_id, notification.item_id, "✉️ Notifying…");
+            let item = &group.item;
+            info!(
+                subscription.chat_id,
+                item.id,
+                n_duplicates = group.duplicates.len(),
+                "✉️ Enqueueing the notification…"
+            );
+            let seller_links = self.seller_links(subscription.chat_id, search_query, item).await?;
+            let picture_urls = item.pictures(chat.sends_album);
             let description = render::item_description(
-                &item,
+                item,
                 &ManageSearchQuery::new(&search_query.text, &[&unsubscribe_link]),
+                &seller_links,
+                &group.duplicates,
+                Notification::max_text_length(picture_urls),
             );
-            telegram::notification::Notification::builder()
-                .chat_id(Cow::Owned(subscription.chat_id.into()))
-                .text(description.into())
-                .maybe_picture_url(item.picture_url.as_ref())
-                .parse_mode(ParseMode::Html)
-                .build()
-                .react_to(&self.telegram)
-                .await?;
-            Notifications(&mut connection).upsert(&notification).await?;
+            let notification =
+                db::Notification { item_id: item.id.clone(), chat_id: subscription.chat_id };
+            let mut entry = OutboxEntry {
+                id: 0,
+                item_id: Some(item.id.clone()),
+                chat_id: subscription.chat_id,
+                text: description,
+                picture_urls: picture_urls.to_vec(),
+                n_attempts: 0,
+                next_attempt_at: Utc::now(),
+            };
+            usage.n_notifications_last_hour += 1;
+            let is_quota_reached = !self.reloadable.borrow().quotas.allows_notification(usage);
+            {
+                // Record the notification and enqueue it at once, the outbox sender delivers it.
+                // The duplicates are shown in the same message, so they are recorded as grouped into it.
+                let mut transaction = self.db.begin().await?;
+                Notifications(&mut transaction).upsert(&notification).await?;
+                for duplicate in &group.duplicates {
+                    let notification = db::Notification {
+                        item_id: duplicate.id.clone(),
+                        chat_id: subscription.chat_id,
+                    };
+                    Notifications(&mut transaction).upsert_grouped(&notification, &item.id).await?;
+                }
+                Outbox(&mut transaction).insert(&mut entry).await?;
+                if is_quota_reached {
+                    // Just reached the limit, let the user know once:
+                    let mut warning = OutboxEntry {
+                        id: 0,
+                        item_id: None,
+                        chat_id: subscription.chat_id,
+                        text: "🔕 You have reached the hourly limit of notifications, the rest will follow later".to_string(),
+                        picture_urls: Vec::new(),
+                        n_attempts: 0,
+                        next_attempt_at: Utc::now(),
+                    };
+                    Outbox(&mut transaction).insert(&mut warning).await?;
+                }
+                transaction.commit().await.context("failed to enqueue the notification")?;
+            }
         }
 
         info!(subscription.chat_id, search_query.text, "✅ Done");
         Ok(())
     }
+
+    /// Build the links to follow and block the item's seller.
+    ///
+    /// The follow link is omitted if the seller is unknown, or the subscription already follows them.
+    async fn seller_links(
+        &self,
+        chat_id: i64,
+        search_query: &SearchQuery,
+        item: &marketplace::item::Item,
+    ) -> Result<Vec<CommandLink>> {
+        let command_builder = self.command_builder.for_chat(chat_id);
+        let mut links = Vec::new();
+        if let Some(seller_query) =
+            item.seller_query().filter(|seller_query| seller_query.hash != search_query.hash)
+        {
+            SearchQueries(&mut *self.db.connection().await?).upsert(&seller_query).await?;
+            links.push(command_builder.follow_seller_link(seller_query.hash));
+        }
+        if let Some(seller) = &item.seller {
+            links.extend(command_builder.block_seller_link(item.marketplace_id, seller.key()));
+        }
+        Ok(links)
+    }
+
+    /// Enrich the new items and group the duplicates together.
+    ///
+    /// The items, which look like the already notified ones, are skipped.
+    /// They are not marked as notified, so they get checked again when the window passes.
+    ///
+    /// The items, which failed to enrich or turned out to be from a blocked seller, are skipped too.
+    ///
+    /// Stops before enriching the next item, once there are as many groups as the chat may still be notified about.
+    async fn group_duplicates(
+        &self,
+        new_items: Vec<marketplace::item::Item>,
+        blocked_sellers: &[BlockedSeller],
+        notified: &[Fingerprint],
+        max_groups: Option<u32>,
+    ) -> Result<Vec<Group>> {
+        let mut groups: Vec<Group> = Vec::new();
+        for item in new_items {
+            if max_groups.is_some_and(|max_groups| groups.len() >= max_groups as usize) {
+                info!("🔕 Notification quota is reached, leaving the rest for later");
+                break;
+            }
+            let Some(item) = self.marketplaces.enrich(&self.db, item).await else {
+                continue;
+            };
+            if blocked_sellers.iter().any(|seller| seller.blocks(&item)) {
+                info!(item.id, "🚫 Skipping the item from a blocked seller");
+                continue;
+            }
+            Items(&mut *self.db.connection().await?).upsert(&Item::seen(&item, Utc::now())).await?;
+            let fingerprint = self.fingerprint(&item).await?;
+            if notified.iter().any(|notified| fingerprint.is_duplicate_of(notified)) {
+                info!(item.id, "🔁 Skipping the duplicate of a notified item");
+            } else if let Some(group) =
+                groups.iter_mut().find(|group| fingerprint.is_duplicate_of(&group.fingerprint))
+            {
+                info!(item.id, group.item.id, "🔁 Grouping the duplicate");
+                group.duplicates.push(item);
+            } else {
+                groups.push(Group { item, fingerprint, duplicates: Vec::new() });
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Fetch the cached item fingerprint, or calculate and cache it.
+    async fn fingerprint(&self, item: &marketplace::item::Item) -> Result<Fingerprint> {
+        if let Some(fingerprint) =
+            ItemFingerprints(&mut *self.db.connection().await?).fetch(&item.id).await?
+        {
+            return Ok(fingerprint);
+        }
+        let picture_hash = match item.picture_urls.first() {
+            Some(picture_url) => {
+                match fingerprint::fetch_picture_hash(&self.client, picture_url).await {
+                    Ok(picture_hash) => Some(picture_hash),
+                    Err(error) => {
+                        warn!(item.id, "⚠️ Failed to hash the picture: {error:#}");
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        let fingerprint = Fingerprint::of(item, picture_hash);
+        ItemFingerprints(&mut *self.db.connection().await?).upsert(&item.id, &fingerprint).await?;
+        Ok(fingerprint)
+    }
+}
+
+/// New item together with its duplicates from the same search.
+struct Group {
+    item: marketplace::item::Item,
+    fingerprint: Fingerprint,
+    duplicates: Vec<marketplace::item::Item>,
 }
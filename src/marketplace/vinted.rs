@@ -1,39 +1,81 @@
-use std::iter::once;
+use std::{collections::HashSet, iter::once, str::FromStr};
 
 use async_trait::async_trait;
 use bon::Builder;
+use chrono::{TimeDelta, Utc};
+use sqlx::SqliteConnection;
 
 use crate::{
-    db::{Db, KeyValues, SearchQuery},
+    db::{Db, KeyValues, SearchQuery, VintedLookups},
     heartbeat::Heartbeat,
-    marketplace::{Marketplace, item::Item, vinted::search::SearchRequest},
+    marketplace::{
+        Dropped,
+        Marketplace,
+        NormalisedQuery,
+        SearchOutcome,
+        item::{Details, Item},
+        vinted::search::SearchRequest,
+    },
     prelude::*,
 };
 
+/// How long the brand and size lookups stay cached.
+const LOOKUP_TTL: TimeDelta = TimeDelta::days(30);
+
 mod client;
+mod details;
+mod domain;
 mod error;
+mod filters;
+mod lookup;
 mod search;
 
 pub use self::{
     client::{AuthenticationTokens, VintedClient},
+    domain::Domain as VintedDomain,
     error::Error as VintedError,
+    filters::Filters as VintedFilters,
 };
+use self::{domain::Domain, filters::Filters};
 
 #[derive(Clone, Builder)]
 pub struct Vinted {
     client: VintedClient,
+
+    /// Country domains to search on.
+    domains: Vec<Domain>,
+
     search_limit: u32,
     db: Db,
     heartbeat: Heartbeat,
 }
 
 impl Vinted {
-    async fn refresh_tokens(&self, refresh_token: &str) -> Result<AuthenticationTokens> {
-        let mut db = self.db.connection().await;
-        let mut key_values = KeyValues(&mut db);
-        match self.client.refresh_token(refresh_token).await {
+    /// Fetch the domain's stored tokens.
+    ///
+    /// Falls back to the legacy tokens for the default domain.
+    pub async fn fetch_tokens(
+        connection: &mut SqliteConnection,
+        domain: &Domain,
+    ) -> Result<Option<AuthenticationTokens>> {
+        let mut key_values = KeyValues(connection);
+        match key_values.fetch_as(&AuthenticationTokens::key(domain)).await? {
+            Some(auth_tokens) => Ok(Some(auth_tokens)),
+            None if *domain == Domain::default() => key_values.fetch().await,
+            None => Ok(None),
+        }
+    }
+
+    async fn refresh_tokens(
+        &self,
+        domain: &Domain,
+        refresh_token: &str,
+    ) -> Result<AuthenticationTokens> {
+        match self.client.refresh_token(domain, refresh_token).await {
             Ok(auth_tokens) => {
-                key_values.upsert(&auth_tokens).await?;
+                KeyValues(&mut *self.db.connection().await?)
+                    .upsert_as(&AuthenticationTokens::key(domain), &auth_tokens)
+                    .await?;
                 Ok(auth_tokens)
             }
             Err(error) => {
@@ -41,57 +83,231 @@ impl Vinted {
             }
         }
     }
+
+    /// Call the API with the domain's access token, refreshing the tokens once if needed.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the domain is not authenticated.
+    async fn call_authenticated<T, F>(
+        &self,
+        domain: &Domain,
+        call: impl Fn(String) -> F,
+    ) -> Result<Option<T>>
+    where
+        F: Future<Output = Result<T, VintedError>>,
+    {
+        let Some(auth_tokens) =
+            Self::fetch_tokens(&mut *self.db.connection().await?, domain).await?
+        else {
+            warn!(
+                "⚠️ Run `mrktpltsbot vinted authenticate --domain {domain}` to use Vinted search on this domain"
+            );
+            return Ok(None);
+        };
+        match call(auth_tokens.access).await {
+            Ok(value) => Ok(Some(value)),
+            Err(VintedError::Reauthenticate) => {
+                let auth_tokens = self.refresh_tokens(domain, &auth_tokens.refresh).await?;
+                Ok(Some(call(auth_tokens.access).await?))
+            }
+            Err(error) => {
+                bail!("failed to call the API: {error:#}");
+            }
+        }
+    }
+
+    /// Look up the brand or size IDs by the name, using the cache.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the domain is not authenticated.
+    async fn lookup_ids(
+        &self,
+        domain: &Domain,
+        kind: LookupKind,
+        name: &str,
+    ) -> Result<Option<Vec<u64>>> {
+        if let Some(id) = filters::parse_id(name) {
+            return Ok(Some(vec![id]));
+        }
+        let since = Utc::now() - LOOKUP_TTL;
+        if let Some(ids) = VintedLookups(&mut *self.db.connection().await?)
+            .fetch(domain.as_str(), kind.as_str(), name, since)
+            .await?
+        {
+            return Ok(Some(ids));
+        }
+        let ids = self
+            .call_authenticated(domain, |access_token| async move {
+                match kind {
+                    LookupKind::Brand => {
+                        Ok(self.client.brands(domain, &access_token, name).await?.ids_of(name))
+                    }
+                    LookupKind::Size => {
+                        Ok(self.client.size_groups(domain, &access_token).await?.ids_of(name))
+                    }
+                }
+            })
+            .await?;
+        if let Some(ids) = &ids {
+            info!(kind = kind.as_str(), name, ?ids, "🔎 Looked up");
+            VintedLookups(&mut *self.db.connection().await?)
+                .upsert(domain.as_str(), kind.as_str(), name, ids)
+                .await?;
+        }
+        Ok(ids)
+    }
+
+    /// Resolve the query modifiers into the catalog filters.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if nothing could match the filters.
+    async fn resolve_filters(
+        &self,
+        domain: &Domain,
+        query: &NormalisedQuery,
+    ) -> Result<Option<Filters>> {
+        let mut filters = Filters::parse_static(query)?;
+        for (kind, ids) in
+            [(LookupKind::Brand, &mut filters.brand_ids), (LookupKind::Size, &mut filters.size_ids)]
+        {
+            for name in query.modifier(kind.as_str()) {
+                match self.lookup_ids(domain, kind, name).await? {
+                    Some(found_ids) if !found_ids.is_empty() => ids.extend(found_ids),
+                    Some(_) => {
+                        warn!(kind = kind.as_str(), name, "⚠️ Unknown name");
+                        return Ok(None);
+                    }
+                    None => return Ok(None),
+                }
+            }
+        }
+        Ok(Some(filters))
+    }
+
+    #[instrument(skip_all, fields(domain = %domain))]
+    async fn search_on(
+        &self,
+        domain: &Domain,
+        query: &NormalisedQuery,
+        search_text: &str,
+    ) -> Result<Vec<search::Item>> {
+        let Some(filters) = self.resolve_filters(domain, query).await? else {
+            return Ok(vec![]);
+        };
+        let filters = &filters;
+        let search_results = self
+            .call_authenticated(domain, |access_token| async move {
+                SearchRequest::builder()
+                    .search_text(search_text)
+                    .per_page(self.search_limit)
+                    .access_token(&access_token)
+                    .filters(filters)
+                    .build()
+                    .call_on(&self.client, domain)
+                    .await
+            })
+            .await?;
+        Ok(search_results.map_or_else(Vec::new, |search_results| search_results.items))
+    }
+
+    #[instrument(skip_all, fields(domain = %domain, user_id = user_id))]
+    async fn user_items_on(&self, domain: &Domain, user_id: &str) -> Result<Vec<search::Item>> {
+        ensure!(filters::parse_id(user_id).is_some(), "Vinted user ID must be a number");
+        let search_results = self
+            .call_authenticated(domain, |access_token| async move {
+                self.client.user_items(domain, &access_token, user_id, self.search_limit).await
+            })
+            .await?;
+        Ok(search_results.map_or_else(Vec::new, |search_results| search_results.items))
+    }
+}
+
+#[derive(Copy, Clone)]
+enum LookupKind {
+    Brand,
+    Size,
+}
+
+impl LookupKind {
+    /// Modifier key, which is also the cache kind.
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Brand => "brand",
+            Self::Size => "size",
+        }
+    }
 }
 
 #[async_trait]
 impl Marketplace for Vinted {
+    fn id(&self) -> &'static str {
+        "vinted"
+    }
+
+    fn name(&self) -> &'static str {
+        "Vinted"
+    }
+
     async fn check_in(&self) {
         self.heartbeat.check_in().await;
     }
 
-    async fn search(&mut self, query: &SearchQuery) -> Result<Vec<Item>> {
-        let Some(auth_tokens) =
-            KeyValues(&mut *self.db.connection().await).fetch::<AuthenticationTokens>().await?
-        else {
-            warn!("⚠️ Run `mrktpltsbot vinted authenticate` to use Vinted search");
-            return Ok(vec![]);
-        };
+    async fn search_explained(&self, query: &SearchQuery) -> Result<SearchOutcome> {
         let query = query.normalised_query();
         let search_text = query.search_text();
-        let result = SearchRequest::builder()
-            .search_text(&search_text)
-            .per_page(self.search_limit)
-            .access_token(&auth_tokens.access)
-            .build()
-            .call_on(&self.client)
-            .await;
-        let search_results = match result {
-            Ok(search_results) => search_results,
-            Err(VintedError::Reauthenticate) => {
-                let auth_tokens = self.refresh_tokens(&auth_tokens.refresh).await?;
-                SearchRequest::builder()
-                    .search_text(&search_text)
-                    .per_page(self.search_limit)
-                    .access_token(&auth_tokens.access)
-                    .build()
-                    .call_on(&self.client)
-                    .await?
+        let mut fetched_items = Vec::new();
+        if let Some(user_id) = query.seller() {
+            // The user's wardrobe is the same on all the domains:
+            let domain = self.domains.first().context("no Vinted domains configured")?;
+            fetched_items.extend(self.user_items_on(domain, user_id).await?);
+        } else {
+            // One failing domain should not discard the results from the others:
+            let mut errors = Vec::new();
+            for domain in &self.domains {
+                match self.search_on(domain, &query, &search_text).await {
+                    Ok(items) => fetched_items.extend(items),
+                    Err(error) => {
+                        warn!(%domain, "⚠️ Failed to search on the domain: {error:#}");
+                        errors.push(error);
+                    }
+                }
             }
-            Err(error) => {
-                bail!("failed to search: {error:#}");
+            if errors.len() == self.domains.len() {
+                if let Some(error) = errors.pop() {
+                    return Err(error.context("failed to search on all the domains"));
+                }
             }
-        };
-        let n_fetched = search_results.items.len();
-        let items = search_results
-            .items
-            .into_iter()
-            .filter(|item| {
-                query.matches(item.title.split_whitespace().chain(once(item.brand_title.as_str())))
+        }
+        let n_fetched = fetched_items.len();
+
+        // The same item is listed on every domain it ships to:
+        let mut seen_ids = HashSet::new();
+        let mut outcome = SearchOutcome::default();
+        for item in fetched_items.into_iter().filter(|item| seen_ids.insert(item.id)) {
+            let mismatch = query
+                .mismatch(item.title.split_whitespace().chain(once(item.brand_title.as_str())));
+            let item = Item::from(item);
+            match mismatch {
+                None => outcome.items.push(item),
+                Some(reason) => outcome.dropped.push(Dropped::new(item, reason)),
+            }
+        }
+        info!(search_text, n_fetched, n_filtered = outcome.items.len(), "🛍️ Fetched");
+        Ok(outcome)
+    }
+
+    /// Fetch the item from the domain, on which it was found.
+    async fn fetch_details(&self, item: &Item) -> Result<Option<Details>> {
+        let id = item.id.strip_prefix("vinted::").context("not a Vinted item")?;
+        let domain = &Domain::from_str(item.url.host_str().context("missing item URL host")?)?;
+        let response = self
+            .call_authenticated(domain, |access_token| async move {
+                self.client.item(domain, &access_token, id).await
             })
-            .map(Item::from)
-            .collect::<Vec<Item>>();
-        info!(search_text, n_fetched, n_filtered = items.len(), "🛍️ Fetched");
-        self.check_in().await;
-        Ok(items)
+            .await?;
+        Ok(response.map(|response| response.item.into()))
     }
 }
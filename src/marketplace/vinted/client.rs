@@ -2,12 +2,16 @@ use bon::Builder;
 use prost::Message;
 use reqwest::{StatusCode, header};
 use reqwest_middleware::ClientWithMiddleware;
+use serde::de::DeserializeOwned;
 use url::Url;
 
 use crate::{
     db::KeyedMessage,
     marketplace::vinted::{
         VintedError,
+        details::ItemResponse,
+        domain::Domain,
+        lookup::{Brands, SizeGroups},
         search::{SearchRequest, SearchResults},
     },
     prelude::*,
@@ -17,12 +21,16 @@ use crate::{
 pub struct VintedClient(pub ClientWithMiddleware);
 
 impl VintedClient {
-    #[instrument(skip_all, err(level = Level::DEBUG))]
-    pub async fn refresh_token(&self, refresh_token: &str) -> Result<AuthenticationTokens> {
+    #[instrument(skip_all, fields(domain = %domain), err(level = Level::DEBUG))]
+    pub async fn refresh_token(
+        &self,
+        domain: &Domain,
+        refresh_token: &str,
+    ) -> Result<AuthenticationTokens> {
         info!("🔐 Refreshing token…");
         let response = self
             .0
-            .post("https://www.vinted.com/web/api/auth/refresh")
+            .post(domain.url("/web/api/auth/refresh"))
             .header(header::COOKIE, format!("refresh_token_web={refresh_token}"))
             .send()
             .await?
@@ -42,9 +50,10 @@ impl VintedClient {
             .build())
     }
 
-    #[instrument(skip_all)]
+    #[instrument(skip_all, fields(domain = %domain))]
     pub async fn search(
         &self,
+        domain: &Domain,
         access_token: &str,
         request: &SearchRequest<'_>,
     ) -> Result<SearchResults, VintedError> {
@@ -52,10 +61,71 @@ impl VintedClient {
         let url = {
             let query =
                 serde_qs::to_string(request).context("failed to serialize the search request")?;
-            let mut url = Url::parse("https://www.vinted.nl/api/v2/catalog/items").unwrap();
+            let mut url = domain.url("/api/v2/catalog/items");
             url.set_query(Some(&query));
+            request.filters.append_to(&mut url);
             url
         };
+        self.get_json(url, access_token).await
+    }
+
+    /// Fetch the user's items, newest first.
+    #[instrument(skip_all, fields(domain = %domain, user_id = user_id))]
+    pub async fn user_items(
+        &self,
+        domain: &Domain,
+        access_token: &str,
+        user_id: &str,
+        per_page: u32,
+    ) -> Result<SearchResults, VintedError> {
+        info!(per_page, "🔎 Fetching the user's items…");
+        let mut url = domain.url(&format!("/api/v2/users/{user_id}/items"));
+        url.query_pairs_mut()
+            .append_pair("order", "newest_first")
+            .append_pair("per_page", &per_page.to_string());
+        self.get_json(url, access_token).await
+    }
+
+    /// Look up the brands by the keyword.
+    #[instrument(skip_all, fields(domain = %domain, keyword = keyword))]
+    pub async fn brands(
+        &self,
+        domain: &Domain,
+        access_token: &str,
+        keyword: &str,
+    ) -> Result<Brands, VintedError> {
+        let mut url = domain.url("/api/v2/brands");
+        url.query_pairs_mut().append_pair("keyword", keyword);
+        self.get_json(url, access_token).await
+    }
+
+    /// Fetch all the size groups together with their sizes.
+    #[instrument(skip_all, fields(domain = %domain))]
+    pub async fn size_groups(
+        &self,
+        domain: &Domain,
+        access_token: &str,
+    ) -> Result<SizeGroups, VintedError> {
+        self.get_json(domain.url("/api/v2/size_groups"), access_token).await
+    }
+
+    /// Fetch the item details.
+    #[instrument(skip_all, fields(domain = %domain, id = id))]
+    pub async fn item(
+        &self,
+        domain: &Domain,
+        access_token: &str,
+        id: &str,
+    ) -> Result<ItemResponse, VintedError> {
+        info!("🔎 Fetching the item…");
+        self.get_json(domain.url(&format!("/api/v2/items/{id}")), access_token).await
+    }
+
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: Url,
+        access_token: &str,
+    ) -> Result<T, VintedError> {
         let response = self
             .0
             .get(url)
@@ -68,12 +138,12 @@ impl VintedClient {
             // FIXME: not sure about 403.
             return Err(VintedError::Reauthenticate);
         }
-        let search_results = response
+        let value = response
             .error_for_status()?
             .json()
             .await
-            .context("failed to deserialize search results")?;
-        Ok(search_results)
+            .context("failed to deserialize the response")?;
+        Ok(value)
     }
 }
 
@@ -89,6 +159,14 @@ pub struct AuthenticationTokens {
     pub refresh: String,
 }
 
+impl AuthenticationTokens {
+    /// Key-value store key for the domain's tokens.
+    pub fn key(domain: &Domain) -> String {
+        format!("{}::{domain}", Self::KEY)
+    }
+}
+
 impl KeyedMessage for AuthenticationTokens {
+    /// Legacy key of the tokens, which were stored before the multiple domains were supported.
     const KEY: &'static str = "mrktpltsbot::marketplace::vinted::client::AuthenticationTokens";
 }
@@ -0,0 +1,117 @@
+//! Item details response.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::marketplace::item::{Details, Location, Rating};
+
+#[derive(Debug, Deserialize)]
+pub struct ItemResponse {
+    pub item: Item,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Item {
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub photos: Vec<Photo>,
+
+    /// Posting date, despite the name, an RFC 3339 timestamp.
+    #[serde(default)]
+    pub created_at_ts: Option<DateTime<Utc>>,
+
+    pub user: User,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Photo {
+    pub full_size_url: url::Url,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct User {
+    /// Share of the positive feedback, from 0 to 1.
+    #[serde(default)]
+    pub feedback_reputation: Option<f64>,
+
+    #[serde(default)]
+    pub feedback_count: u32,
+
+    #[serde(default)]
+    pub city: Option<String>,
+}
+
+impl From<Item> for Details {
+    fn from(item: Item) -> Self {
+        let seller_rating =
+            item.user.feedback_reputation.filter(|_| item.user.feedback_count != 0).map(
+                |reputation| Rating {
+                    score: reputation * 5.0,
+                    n_reviews: item.user.feedback_count,
+                },
+            );
+        Self {
+            description: item.description.filter(|description| !description.is_empty()),
+            picture_urls: item.photos.into_iter().map(|photo| photo.full_size_url).collect(),
+            location: item
+                .user
+                .city
+                .filter(|city| !city.is_empty())
+                .map(|city| Location::builder().toponym(city).build()),
+            posted_at: item.created_at_ts,
+            seller_rating,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn deserialize_item_response_ok() -> Result {
+        // language=json
+        let response: ItemResponse = serde_json::from_str(
+            r#"
+            {
+              "item": {
+                "id": 6245197443,
+                "title": "Unifi u6 pro",
+                "description": "Werkt perfect, inclusief PoE-adapter.",
+                "created_at_ts": "2025-05-01T15:51:41+02:00",
+                "photos": [
+                  {
+                    "id": 25350626557,
+                    "full_size_url": "https://images1.vinted.net/tc/03_006f6_dTDx2rZxo5Ma5tTtWH8rW5v6/1746107501.jpeg"
+                  },
+                  {
+                    "id": 25350626558,
+                    "full_size_url": "https://images1.vinted.net/tc/03_006f6_aBcDeFgHiJkLmNoPqRsTuVwX/1746107502.jpeg"
+                  }
+                ],
+                "user": {
+                  "id": 258360251,
+                  "login": "hertokken",
+                  "feedback_reputation": 0.98,
+                  "feedback_count": 45,
+                  "city": "Amsterdam"
+                }
+              }
+            }
+            "#,
+        )?;
+        let details = Details::from(response.item);
+        assert_eq!(details.description.as_deref(), Some("Werkt perfect, inclusief PoE-adapter."));
+        assert_eq!(details.picture_urls.len(), 2);
+        assert_eq!(details.location.unwrap().toponym, "Amsterdam");
+        assert_eq!(details.posted_at.unwrap().to_rfc3339(), "2025-05-01T13:51:41+00:00");
+        let rating = details.seller_rating.unwrap();
+        assert!((rating.score - 4.9).abs() < 1e-9);
+        assert_eq!(rating.n_reviews, 45);
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use url::Url;
+
+use crate::prelude::*;
+
+/// Vinted country domain, identified by its top-level domain, for example `nl` or `co.uk`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Domain(String);
+
+impl Domain {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn base_url(&self) -> Url {
+        Url::parse(&format!("https://www.vinted.{}", self.0)).unwrap()
+    }
+
+    pub fn url(&self, path: &str) -> Url {
+        self.base_url().join(path).unwrap()
+    }
+}
+
+impl Default for Domain {
+    fn default() -> Self {
+        Self("nl".to_string())
+    }
+}
+
+impl FromStr for Domain {
+    type Err = Error;
+
+    fn from_str(domain: &str) -> Result<Self> {
+        let domain = domain.trim().trim_start_matches("www.vinted.").trim_start_matches('.');
+        ensure!(
+            !domain.is_empty()
+                && domain.split('.').all(|label| {
+                    !label.is_empty() && label.chars().all(|char| char.is_ascii_alphabetic())
+                }),
+            "invalid Vinted domain: `{domain}`",
+        );
+        Ok(Self(domain.to_ascii_lowercase()))
+    }
+}
+
+impl Display for Domain {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ok() -> Result {
+        assert_eq!("BE".parse::<Domain>()?.to_string(), "be");
+        assert_eq!(
+            "www.vinted.co.uk".parse::<Domain>()?.url("/api/v2/catalog/items").as_str(),
+            "https://www.vinted.co.uk/api/v2/catalog/items",
+        );
+        assert!("vinted.nl/".parse::<Domain>().is_err());
+        assert!("".parse::<Domain>().is_err());
+        Ok(())
+    }
+}
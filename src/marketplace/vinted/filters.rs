@@ -0,0 +1,197 @@
+//! Vinted catalog filters from the search query modifiers.
+//!
+//! Supported modifiers:
+//!
+//! - `brand:nike`, `size:m` – names are looked up via the API and cached, IDs are taken as is
+//! - `catalog:1206` – catalog IDs only
+//! - `color:black`, `status:very_good` – names from the static tables below, or IDs
+//! - `price:10-50`, `price:-50`, `price:10-` – price range in euros
+
+use rust_decimal::Decimal;
+use url::Url;
+
+use crate::{marketplace::NormalisedQuery, prelude::*};
+
+/// Colour names and IDs.
+const COLORS: &[(&str, u64)] = &[
+    ("black", 1),
+    ("brown", 2),
+    ("grey", 3),
+    ("beige", 4),
+    ("pink", 5),
+    ("purple", 6),
+    ("red", 7),
+    ("yellow", 8),
+    ("blue", 9),
+    ("green", 10),
+    ("orange", 11),
+    ("white", 12),
+    ("silver", 13),
+    ("gold", 14),
+    ("multi", 15),
+    ("khaki", 16),
+    ("turquoise", 17),
+    ("cream", 20),
+    ("apricot", 21),
+    ("coral", 22),
+    ("burgundy", 23),
+    ("rose", 24),
+    ("lilac", 25),
+    ("light_blue", 26),
+    ("navy", 27),
+    ("dark_green", 28),
+    ("mustard", 29),
+    ("mint", 30),
+];
+
+/// Item status names and IDs.
+const STATUSES: &[(&str, &[u64])] = &[
+    ("new", &[6, 1]),
+    ("new_with_tags", &[6]),
+    ("new_without_tags", &[1]),
+    ("very_good", &[2]),
+    ("good", &[3]),
+    ("satisfactory", &[4]),
+];
+
+/// Filters resolved into the IDs.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Filters {
+    pub brand_ids: Vec<u64>,
+    pub size_ids: Vec<u64>,
+    pub catalog_ids: Vec<u64>,
+    pub color_ids: Vec<u64>,
+    pub status_ids: Vec<u64>,
+    pub price_from: Option<Decimal>,
+    pub price_to: Option<Decimal>,
+}
+
+impl Filters {
+    /// Parse the static filters from the query, leaving the brands and sizes to the caller.
+    ///
+    /// Fails on an invalid catalog ID or price, or an unknown colour or status name.
+    pub fn parse_static(query: &NormalisedQuery) -> Result<Self> {
+        let mut this = Self::default();
+        for value in query.modifier("catalog") {
+            this.catalog_ids
+                .push(value.parse().with_context(|| format!("catalog must be an ID: `{value}`"))?);
+        }
+        for value in query.modifier("color") {
+            let id = parse_id(value)
+                .or_else(|| lookup(COLORS, value))
+                .with_context(|| format!("unknown colour: `{value}`"))?;
+            this.color_ids.push(id);
+        }
+        for value in query.modifier("status") {
+            match parse_id(value) {
+                Some(id) => this.status_ids.push(id),
+                None => this.status_ids.extend(
+                    lookup(STATUSES, value)
+                        .with_context(|| format!("unknown status: `{value}`"))?,
+                ),
+            }
+        }
+        (this.price_from, this.price_to) = query.price_range()?;
+        Ok(this)
+    }
+
+    /// Append the filters to the search URL.
+    pub fn append_to(&self, url: &mut Url) {
+        let mut query_pairs = url.query_pairs_mut();
+        for (key, ids) in [
+            ("brand_ids[]", &self.brand_ids),
+            ("size_ids[]", &self.size_ids),
+            ("catalog_ids[]", &self.catalog_ids),
+            ("color_ids[]", &self.color_ids),
+            ("status_ids[]", &self.status_ids),
+        ] {
+            for id in ids {
+                query_pairs.append_pair(key, &id.to_string());
+            }
+        }
+        if let Some(price_from) = self.price_from {
+            query_pairs.append_pair("price_from", &price_from.to_string());
+        }
+        if let Some(price_to) = self.price_to {
+            query_pairs.append_pair("price_to", &price_to.to_string());
+        }
+        if self.price_from.is_some() || self.price_to.is_some() {
+            query_pairs.append_pair("currency", "EUR");
+        }
+    }
+}
+
+/// Parse the value as a numeric ID.
+pub fn parse_id(value: &str) -> Option<u64> {
+    value.parse().ok()
+}
+
+fn lookup<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table.iter().find(|(known_name, _)| *known_name == name).map(|(_, value)| *value)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn parse_static_ok() -> Result {
+        let query = NormalisedQuery::parse(
+            "jacket brand:nike catalog:1206 color:black,27 status:very_good price:10-50.5",
+        );
+        let filters = Filters::parse_static(&query)?;
+        assert_eq!(
+            filters,
+            Filters {
+                catalog_ids: vec![1206],
+                color_ids: vec![27, 1],
+                status_ids: vec![2],
+                price_from: Some(dec!(10)),
+                price_to: Some(dec!(50.5)),
+                ..Filters::default()
+            },
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_price_to_ok() -> Result {
+        let query = NormalisedQuery::parse("jacket price:50");
+        let filters = Filters::parse_static(&query)?;
+        assert_eq!(filters.price_from, None);
+        assert_eq!(filters.price_to, Some(dec!(50)));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_unknown_color_fails() {
+        let query = NormalisedQuery::parse("jacket color:octarine");
+        assert!(Filters::parse_static(&query).is_err());
+    }
+
+    #[test]
+    fn parse_invalid_catalog_fails() {
+        let query = NormalisedQuery::parse("jacket catalog:abc");
+        assert!(Filters::parse_static(&query).is_err());
+    }
+
+    #[test]
+    fn append_to_ok() {
+        let filters = Filters {
+            brand_ids: vec![53],
+            size_ids: vec![207, 208],
+            price_to: Some(dec!(50)),
+            ..Filters::default()
+        };
+        let mut url = Url::parse("https://www.vinted.nl/api/v2/catalog/items?per_page=10").unwrap();
+        filters.append_to(&mut url);
+        assert_eq!(
+            url.query(),
+            Some(
+                "per_page=10&brand_ids%5B%5D=53&size_ids%5B%5D=207&size_ids%5B%5D=208&price_to=50&currency=EUR"
+            ),
+        );
+    }
+}
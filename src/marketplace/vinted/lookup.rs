@@ -0,0 +1,84 @@
+//! Responses of the filter lookups.
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Brands {
+    pub brands: Vec<Brand>,
+}
+
+impl Brands {
+    /// IDs of the brands, which exactly match the name.
+    pub fn ids_of(&self, name: &str) -> Vec<u64> {
+        self.brands
+            .iter()
+            .filter(|brand| brand.title.eq_ignore_ascii_case(name))
+            .map(|brand| brand.id)
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Brand {
+    pub id: u64,
+    pub title: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SizeGroups {
+    pub size_groups: Vec<SizeGroup>,
+}
+
+impl SizeGroups {
+    /// IDs of the sizes, which match the name in any of the groups.
+    ///
+    /// For example, `M` exists for both women's and men's clothing.
+    pub fn ids_of(&self, name: &str) -> Vec<u64> {
+        self.size_groups
+            .iter()
+            .flat_map(|group| &group.sizes)
+            .filter(|size| size.title.eq_ignore_ascii_case(name))
+            .map(|size| size.id)
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SizeGroup {
+    pub sizes: Vec<Size>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Size {
+    pub id: u64,
+    pub title: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn brands_ids_of_ok() -> Result {
+        // language=json
+        let brands: Brands = serde_json::from_str(
+            r#"{"brands": [{"id": 53, "title": "Nike", "slug": "nike"}, {"id": 2319, "title": "Nike ACG", "slug": "nike-acg"}]}"#,
+        )?;
+        assert_eq!(brands.ids_of("nike"), [53]);
+        Ok(())
+    }
+
+    #[test]
+    fn size_groups_ids_of_ok() -> Result {
+        // language=json
+        let size_groups: SizeGroups = serde_json::from_str(
+            r#"{"size_groups": [
+                {"id": 4, "description": "Women's tops", "sizes": [{"id": 206, "title": "S"}, {"id": 207, "title": "M"}]},
+                {"id": 7, "description": "Men's tops", "sizes": [{"id": 208, "title": "M"}, {"id": 209, "title": "L"}]}
+            ]}"#,
+        )?;
+        assert_eq!(size_groups.ids_of("m"), [207, 208]);
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ use url::Url;
 use crate::{
     marketplace::{
         item::Amount,
-        vinted::{VintedError, client::VintedClient},
+        vinted::{VintedError, client::VintedClient, domain::Domain, filters::Filters},
     },
     prelude::*,
 };
@@ -23,13 +23,20 @@ pub struct SearchRequest<'a> {
 
     pub search_text: &'a str,
 
+    #[serde(skip)]
+    pub filters: &'a Filters,
+
     #[builder(default = Order::NewestFirst)]
     pub order: Order,
 }
 
 impl SearchRequest<'_> {
-    pub async fn call_on(&self, client: &VintedClient) -> Result<SearchResults, VintedError> {
-        client.search(self.access_token, self).await
+    pub async fn call_on(
+        &self,
+        client: &VintedClient,
+        domain: &Domain,
+    ) -> Result<SearchResults, VintedError> {
+        client.search(domain, self.access_token, self).await
     }
 }
 
@@ -61,9 +68,10 @@ impl From<Item> for crate::marketplace::item::Item {
     fn from(item: Item) -> Self {
         Self::builder()
             .id(format!("vinted::{}", item.id))
+            .marketplace_id("vinted")
             .url(item.url)
             .title(item.title)
-            .picture_url(item.photo.full_size_url)
+            .picture_urls(vec![item.photo.full_size_url])
             .condition(item.status.into())
             .delivery(crate::marketplace::item::Delivery::ShippingOnly)
             .price(item.price.into())
@@ -92,13 +100,18 @@ pub struct Photo {
 
 #[derive(Debug, Deserialize)]
 pub struct User {
+    pub id: u64,
     pub login: String,
     pub profile_url: Url,
 }
 
 impl From<User> for crate::marketplace::item::Seller {
     fn from(user: User) -> Self {
-        Self::builder().username(user.login).profile_url(user.profile_url).build()
+        Self::builder()
+            .id(user.id.to_string())
+            .username(user.login)
+            .profile_url(user.profile_url)
+            .build()
     }
 }
 
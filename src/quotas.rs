@@ -0,0 +1,93 @@
+//! Per-chat limits on subscriptions and notifications.
+
+use chrono::{TimeDelta, Utc};
+use sqlx::SqliteConnection;
+
+use crate::{
+    db::{Notifications, Subscriptions},
+    prelude::*,
+};
+
+/// Per-chat quotas, [`None`] means unlimited.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Quotas {
+    pub max_subscriptions: Option<u32>,
+    pub max_notifications_per_hour: Option<u32>,
+}
+
+impl Quotas {
+    /// Check whether the chat may have one more subscription.
+    pub fn allows_subscription(&self, usage: Usage) -> bool {
+        self.max_subscriptions.is_none_or(|max| usage.n_subscriptions < max)
+    }
+
+    /// Check whether the chat may receive one more notification.
+    pub fn allows_notification(&self, usage: Usage) -> bool {
+        self.max_notifications_per_hour.is_none_or(|max| usage.n_notifications_last_hour < max)
+    }
+
+    /// Number of notifications, which the chat may still receive this hour.
+    pub fn remaining_notifications(&self, usage: Usage) -> Option<u32> {
+        self.max_notifications_per_hour
+            .map(|max| max.saturating_sub(usage.n_notifications_last_hour))
+    }
+}
+
+/// Current quota usage of a chat.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Usage {
+    pub n_subscriptions: u32,
+    pub n_notifications_last_hour: u32,
+}
+
+impl Usage {
+    #[instrument(skip_all, fields(chat_id = chat_id))]
+    pub async fn fetch(connection: &mut SqliteConnection, chat_id: i64) -> Result<Self> {
+        let n_subscriptions = Subscriptions(connection).count_of(chat_id).await?;
+        let n_notifications_last_hour = Notifications(connection)
+            .count_since(chat_id, Utc::now() - TimeDelta::hours(1))
+            .await?;
+        Ok(Self { n_subscriptions, n_notifications_last_hour })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_ok() {
+        let usage = Usage { n_subscriptions: 1000, n_notifications_last_hour: 1000 };
+        assert!(Quotas::default().allows_subscription(usage));
+        assert!(Quotas::default().allows_notification(usage));
+        assert_eq!(Quotas::default().remaining_notifications(usage), None);
+    }
+
+    #[test]
+    fn limited_ok() {
+        let quotas = Quotas { max_subscriptions: Some(2), max_notifications_per_hour: Some(10) };
+        assert!(quotas.allows_subscription(Usage { n_subscriptions: 1, ..Usage::default() }));
+        assert!(!quotas.allows_subscription(Usage { n_subscriptions: 2, ..Usage::default() }));
+        assert!(
+            quotas.allows_notification(Usage { n_notifications_last_hour: 9, ..Usage::default() })
+        );
+        assert!(
+            !quotas
+                .allows_notification(Usage { n_notifications_last_hour: 10, ..Usage::default() })
+        );
+        assert_eq!(
+            quotas.remaining_notifications(Usage {
+                n_notifications_last_hour: 7,
+                ..Usage::default()
+            }),
+            Some(3),
+        );
+        assert_eq!(
+            quotas.remaining_notifications(Usage {
+                n_notifications_last_hour: 12,
+                ..Usage::default()
+            }),
+            Some(0),
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Periodic pruning of the stale items.
+
+use std::time::Duration;
+
+use bon::Builder;
+use chrono::{TimeDelta, Utc};
+use tokio::time::sleep;
+
+use crate::{
+    db::{Db, Items},
+    prelude::*,
+};
+
+#[derive(Builder)]
+pub struct Retention {
+    db: Db,
+
+    /// Items, which have not been seen for this period, are deleted.
+    ///
+    /// [`None`] keeps the items forever.
+    period: Option<TimeDelta>,
+
+    /// Interval between the prunings.
+    #[builder(default = Duration::from_secs(3600))]
+    interval: Duration,
+}
+
+impl Retention {
+    pub async fn run(self) {
+        let Some(period) = self.period else {
+            info!("♾️ Retention is disabled, the items are kept forever");
+            return;
+        };
+        info!(?period, ?self.interval, "🔄 Running the retention…");
+        loop {
+            if let Err(error) = self.prune(period).await {
+                error!("‼️ Failed to prune the database: {error:#}");
+            }
+            sleep(self.interval).await;
+        }
+    }
+
+    async fn prune(&self, period: TimeDelta) -> Result {
+        let pruned =
+            Items(&mut *self.db.connection().await?).prune(Utc::now() - period, false).await?;
+        info!(pruned.n_items, pruned.n_notifications, "🧹 Pruned the stale items");
+        if pruned.n_items != 0 {
+            self.db.incremental_vacuum().await?;
+        }
+        Ok(())
+    }
+}
@@ -3,8 +3,11 @@ pub mod commands;
 pub mod methods;
 pub mod notification;
 pub mod objects;
+pub mod outbox;
 pub mod render;
-mod result;
+pub mod result;
+pub mod router;
+pub mod webhook;
 
 use std::fmt::Debug;
 
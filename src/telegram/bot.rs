@@ -1,28 +1,60 @@
-use std::{borrow::Cow, collections::HashSet};
+use std::{borrow::Cow, time::Duration};
 
 use bon::bon;
+use chrono::{TimeDelta, Utc};
 use maud::{Render, html};
+use secrecy::ExposeSecret;
+use tokio::{
+    sync::{mpsc, watch},
+    time::timeout,
+};
 
 use crate::{
-    db::{Db, SearchQueries, SearchQuery, Subscription, Subscriptions},
+    config::Reloadable,
+    db::{
+        AuthorizedChat,
+        AuthorizedChats,
+        BlockedSeller,
+        BlockedSellers,
+        Chats,
+        Db,
+        Invite,
+        Invites,
+        Item,
+        Items,
+        SearchQueries,
+        SearchQuery,
+        Subscription,
+        Subscriptions,
+    },
     heartbeat::Heartbeat,
-    marketplace::{Marketplace, Marktplaats, Vinted},
+    marketplace::{Marketplace, Marketplaces, VintedFilters},
     prelude::*,
+    quotas::Usage,
     telegram::{
         Telegram,
-        commands::{CommandBuilder, CommandPayload, SubscriptionAction},
+        commands::{
+            CommandBuilder,
+            CommandPayload,
+            HistoryCommand,
+            SellerAction,
+            SellerCommand,
+            SubscriptionAction,
+        },
         methods::{
             AllowedUpdate,
+            DeleteWebhook,
             GetUpdates,
             Method,
             SendMessage,
             SetMyCommands,
             SetMyDescription,
+            SetWebhook,
         },
         notification::Notification,
         objects::{
-            BotCommand,
             ChatId,
+            ChatKind,
             LinkPreviewOptions,
             ParseMode,
             ReplyParameters,
@@ -31,22 +63,28 @@ use crate::{
         },
         render,
         render::{DELIMITER, ManageSearchQuery},
+        router::{Command, parse_switch, strip_mention},
+        webhook::Webhook,
     },
 };
 
+/// Number of items on a `/history` page.
+const HISTORY_PAGE_SIZE: u32 = 10;
+
 /// Telegram [`Message`] bot.
 ///
 /// It listens to Telegram [`Update`]'s and reacts on them.
-#[derive(Clone)]
 pub struct Bot {
     telegram: Telegram,
-    authorized_chat_ids: HashSet<i64>,
+    reloadable: watch::Receiver<Reloadable>,
     db: Db,
-    marktplaats: Marktplaats,
-    vinted: Vinted,
+    marketplaces: Marketplaces,
     poll_timeout_secs: u64,
     heartbeat: Heartbeat,
     command_builder: CommandBuilder,
+
+    /// Updates received via the webhook, or [`None`] for long polling.
+    webhook_updates: Option<mpsc::Receiver<Update>>,
 }
 
 #[bon]
@@ -56,11 +94,11 @@ impl Bot {
         telegram: Telegram,
         command_builder: CommandBuilder,
         db: Db,
-        marktplaats: Marktplaats,
-        vinted: Vinted,
+        marketplaces: Marketplaces,
         heartbeat: Heartbeat,
-        authorized_chat_ids: HashSet<i64>,
+        reloadable: watch::Receiver<Reloadable>,
         poll_timeout_secs: u64,
+        webhook: Option<Webhook>,
     ) -> Result<Self> {
         SetMyDescription::builder()
             .description("👋 This is a private bot for Marktplaats\n\nFeel free to set up your own instance from https://github.com/eigenein/mrktpltsbot")
@@ -69,23 +107,40 @@ impl Bot {
             .await
             .context("failed to set the bot's description")?;
         SetMyCommands::builder()
-            .commands(&[&BotCommand::builder()
-                .command("manage")
-                .description("List and manage your subscriptions")
-                .build()])
+            .commands(&Command::REGISTERED.iter().collect::<Vec<_>>())
             .build()
             .call_on(&telegram)
             .await
             .context("failed to set the bot's commands")?;
+        let webhook_updates = if let Some(webhook) = webhook {
+            let updates = webhook.spawn().await?;
+            SetWebhook::builder()
+                .url(webhook.url.as_str())
+                .allowed_updates(&[AllowedUpdate::Message])
+                .secret_token(webhook.secret_token.expose_secret())
+                .build()
+                .call_on(&telegram)
+                .await
+                .context("failed to set the webhook")?;
+            Some(updates)
+        } else {
+            // Long polling does not work while a webhook is set:
+            DeleteWebhook::builder()
+                .build()
+                .call_on(&telegram)
+                .await
+                .context("failed to delete the webhook")?;
+            None
+        };
         Ok(Self {
             telegram,
-            authorized_chat_ids,
+            reloadable,
             db,
-            marktplaats,
-            vinted,
+            marketplaces,
             poll_timeout_secs,
             heartbeat,
             command_builder,
+            webhook_updates,
         })
     }
 }
@@ -94,9 +149,35 @@ impl Bot {
     /// Run the bot indefinitely.
     pub async fn run(mut self) {
         info!(me = self.command_builder.url().as_str(), "🔄 Running Telegram bot…");
-        let mut offset = 0;
+        if let Some(updates) = self.webhook_updates.take() {
+            self.receive_updates(updates).await;
+        } else {
+            let mut offset = 0;
+            loop {
+                offset = self.handle_updates(offset).await;
+            }
+        }
+    }
+
+    /// Handle the updates received via the webhook until the server stops.
+    async fn receive_updates(&mut self, mut updates: mpsc::Receiver<Update>) {
+        let check_in_interval = Duration::from_secs(self.poll_timeout_secs);
         loop {
-            offset = self.handle_updates(offset).await;
+            // Check in periodically, even if there are no updates:
+            match timeout(check_in_interval, updates.recv()).await {
+                Ok(Some(update)) => {
+                    self.heartbeat.check_in().await;
+                    self.handle_update(update).await;
+                }
+                Ok(None) => {
+                    error!("‼️ The webhook server has stopped");
+                    break;
+                }
+                Err(_) => {
+                    debug!("📭 Received no Telegram updates");
+                    self.heartbeat.check_in().await;
+                }
+            }
         }
     }
 
@@ -132,32 +213,64 @@ impl Bot {
         }
 
         for update in updates {
-            let UpdatePayload::Message(message) = update.payload else { continue };
-            let (Some(chat), Some(text)) = (message.chat, message.text) else {
-                warn!(message.id, "⚠️ Message without an associated chat or text");
-                continue;
-            };
-            let ChatId::Integer(chat_id) = chat.id else {
-                warn!(message.id, "⚠️ Username chat IDs are not supported");
-                continue;
-            };
-            if let Err(error) = self.on_message(chat_id, message.id, text.trim()).await {
-                error!(%chat_id, message.id, "‼️ Failed to handle the message: {error:#}");
-                let _ = SendMessage::builder()
-                    .chat_id(Cow::Owned(ChatId::Integer(chat_id)))
-                    .text("💥 An internal error occurred and has been logged")
-                    .build()
-                    .call_and_discard_on(&self.telegram)
-                    .await;
-            }
+            self.handle_update(update).await;
         }
 
         new_offset
     }
 
-    #[instrument(skip_all)]
-    async fn on_message(&mut self, chat_id: i64, message_id: u64, text: &str) -> Result {
-        if !self.authorized_chat_ids.contains(&chat_id) {
+    /// Handle a single update, either polled or received via the webhook.
+    async fn handle_update(&mut self, update: Update) {
+        let UpdatePayload::Message(message) = update.payload else { return };
+        let (Some(chat), Some(text)) = (message.chat, message.text) else {
+            warn!(message.id, "⚠️ Message without an associated chat or text");
+            return;
+        };
+        let ChatId::Integer(chat_id) = chat.id else {
+            warn!(message.id, "⚠️ Username chat IDs are not supported");
+            return;
+        };
+        if let Err(error) = self.on_message(chat_id, chat.kind, message.id, text.trim()).await {
+            error!(%chat_id, message.id, "‼️ Failed to handle the message: {error:#}");
+            let _ = SendMessage::builder()
+                .chat_id(Cow::Owned(ChatId::Integer(chat_id)))
+                .text("💥 An internal error occurred and has been logged")
+                .build()
+                .call_and_discard_on(&self.telegram)
+                .await;
+        }
+    }
+
+    #[instrument(skip_all, fields(?chat_kind))]
+    async fn on_message(
+        &mut self,
+        chat_id: i64,
+        chat_kind: ChatKind,
+        message_id: u64,
+        text: &str,
+    ) -> Result {
+        let me = self.command_builder.username();
+        let request = match Command::parse(text, me) {
+            // Unknown commands without the explicit recipient may be intended for another bot in a group:
+            Some(command) if command.may_be_foreign() && !chat_kind.is_private() => return Ok(()),
+            Some(command) => Request::Command(command),
+            // The command is addressed to another bot:
+            None if text.starts_with('/') => return Ok(()),
+            None if chat_kind.is_private() => Request::Search(Cow::Borrowed(text)),
+            // In groups, people talk to each other, so only react to mentions:
+            None => match strip_mention(text, me) {
+                Some(query) if query.is_empty() => Request::Command(Command::Help),
+                Some(query) => Request::Search(Cow::Owned(query)),
+                None => return Ok(()),
+            },
+        };
+
+        if !self.is_authorized(chat_id).await? {
+            if let Request::Command(Command::Start { payload: Some(payload) }) = request {
+                if self.on_invite_payload(payload, chat_id).await? {
+                    return Ok(());
+                }
+            }
             warn!(chat_id, message_id, text, "⚠️ Received message from an unauthorized chat");
             let chat_id = ChatId::Integer(chat_id);
             let text = render::unauthorized(&chat_id).render().into_string();
@@ -171,16 +284,21 @@ impl Bot {
             .allow_sending_without_reply(true)
             .build();
 
-        if text.starts_with('/') {
-            self.on_command(text, chat_id, reply_parameters).await?;
-        } else {
-            self.on_search(text, chat_id, reply_parameters).await?;
+        match request {
+            Request::Command(command) => {
+                self.on_command(command, chat_id, reply_parameters).await?;
+            }
+            Request::Search(query) => {
+                self.on_search(&query, chat_id, reply_parameters).await?;
+            }
         }
         Ok(())
     }
+
     /// Handle the search request from Telegram.
     ///
-    /// A search request is just a message that is not a command.
+    /// A search request is just a message that is not a command, a `/search` command,
+    /// or a mention in a group.
     #[instrument(skip_all)]
     async fn on_search(
         &mut self,
@@ -189,16 +307,52 @@ impl Bot {
         reply_parameters: ReplyParameters,
     ) -> Result {
         let query = SearchQuery::from(query);
+        let normalised_query = query.normalised_query();
+        if !normalised_query.modifier("site").all(|id| self.marketplaces.is_enabled(id)) {
+            return self
+                .reply(
+                    chat_id,
+                    reply_parameters,
+                    "I do not know some of the marketplaces in site:, see /help for the available ones",
+                )
+                .await;
+        }
+        if !self
+            .marketplaces
+            .enabled()
+            .any(|marketplace| normalised_query.allows_marketplace(marketplace.id()))
+        {
+            return self
+                .reply(
+                    chat_id,
+                    reply_parameters,
+                    "None of the marketplaces supports this query: brand:, size:, catalog:, color:, and status: only work on Vinted, and price: does not work on Kleinanzeigen",
+                )
+                .await;
+        }
+        if let Err(error) = VintedFilters::parse_static(&normalised_query) {
+            return self.reply(chat_id, reply_parameters, format!("😔 {error:#}")).await;
+        }
 
         let mut items = Vec::new();
-        self.marktplaats.search_and_extend_infallible(&query, Some(1), &mut items).await;
-        self.vinted.search_and_extend_infallible(&query, Some(1), &mut items).await;
+        for item in self.marketplaces.search(&query, Some(1)).await {
+            items.extend(self.marketplaces.enrich(&self.db, item).await);
+        }
         info!(query.hash, n_items = items.len(), query.text, "🛍️");
 
-        SearchQueries(&mut *self.db.connection().await).upsert(&query).await?;
+        let chat = {
+            let connection = &mut *self.db.connection().await?;
+            SearchQueries(connection).upsert(&query).await?;
+            for item in &items {
+                Items(connection).upsert(&Item::seen(item, Utc::now())).await?;
+            }
+            let blocked_sellers = BlockedSellers(connection).fetch_all_of(chat_id).await?;
+            items.retain(|item| !blocked_sellers.iter().any(|seller| seller.blocks(item)));
+            Chats(connection).fetch(chat_id).await?
+        };
 
         // We need the subscribe command anyway, even if no listings were found.
-        let subscribe_link = self.command_builder.subscribe_link(query.hash);
+        let subscribe_link = self.command_builder.for_chat(chat_id).subscribe_link(query.hash);
 
         if items.is_empty() {
             let markup = html! {
@@ -217,14 +371,31 @@ impl Bot {
                 .await?;
         } else {
             for item in items {
+                let command_builder = self.command_builder.for_chat(chat_id);
+                let mut seller_links = Vec::new();
+                if let Some(seller_query) =
+                    item.seller_query().filter(|seller_query| seller_query.hash != query.hash)
+                {
+                    SearchQueries(&mut *self.db.connection().await?).upsert(&seller_query).await?;
+                    seller_links.push(command_builder.follow_seller_link(seller_query.hash));
+                }
+                if let Some(seller) = &item.seller {
+                    seller_links.extend(
+                        command_builder.block_seller_link(item.marketplace_id, seller.key()),
+                    );
+                }
+                let picture_urls = item.pictures(chat.sends_album);
                 let description = render::item_description(
                     &item,
                     &ManageSearchQuery::new(&query.text, &[&subscribe_link]),
+                    &seller_links,
+                    &[],
+                    Notification::max_text_length(picture_urls),
                 );
                 Notification::builder()
                     .chat_id(Cow::Owned(chat_id.into()))
                     .text(description.into())
-                    .maybe_picture_url(item.picture_url.as_ref())
+                    .picture_urls(picture_urls)
                     .reply_parameters(reply_parameters)
                     .parse_mode(ParseMode::Html)
                     .build()
@@ -236,112 +407,437 @@ impl Bot {
         Ok(())
     }
 
-    #[instrument(skip_all)]
+    #[instrument(skip_all, fields(?command))]
     async fn on_command(
-        &self,
-        text: &str,
+        &mut self,
+        command: Command<'_>,
         chat_id: i64,
         reply_parameters: ReplyParameters,
     ) -> Result {
-        if text == "/start" {
-            // Just an initial greeting.
-            let chat_id: Cow<'_, ChatId> = Cow::Owned(ChatId::Integer(chat_id));
-            let _ = SendMessage::builder()
-                .chat_id(chat_id.clone())
-                .text("👋")
-                .build()
-                .call_on(&self.telegram)
-                .await?;
-            let _ = SendMessage::builder()
-                .chat_id(chat_id)
-                .text("Just send me a search query to start")
-                .build()
-                .call_on(&self.telegram)
-                .await?;
-        } else if text == "/manage" {
-            self.on_manage_subscriptions(chat_id).await?;
-        } else if let Some(payload) = text.strip_prefix("/start ") {
-            // Command with a payload.
-            let command = CommandPayload::from_base64(payload)?;
-            debug!(?command, "❕ Received command");
-
-            if command.manage.is_some() {
+        let is_admin = self.reloadable.borrow().admin_chat_ids.contains(&chat_id);
+        if command.is_admin_only() && !is_admin {
+            return self
+                .reply(chat_id, reply_parameters, "This command is only available to the admins")
+                .await;
+        }
+        match command {
+            Command::Start { payload: None } => {
+                // Just an initial greeting.
+                let chat_id: Cow<'_, ChatId> = Cow::Owned(ChatId::Integer(chat_id));
+                let _ = SendMessage::builder()
+                    .chat_id(chat_id.clone())
+                    .text("👋")
+                    .build()
+                    .call_on(&self.telegram)
+                    .await?;
+                let _ = SendMessage::builder()
+                    .chat_id(chat_id)
+                    .text("Just send me a search query to start")
+                    .build()
+                    .call_on(&self.telegram)
+                    .await?;
+            }
+            Command::Start { payload: Some(payload) } => {
+                self.on_payload(payload, chat_id).await?;
+            }
+            Command::Help => {
+                let admin_commands = if is_admin { Command::ADMIN } else { &[] };
+                let marketplace_ids: Vec<&str> =
+                    self.marketplaces.enabled().map(Marketplace::id).collect();
+                let text = render::help(Command::REGISTERED, admin_commands, &marketplace_ids)
+                    .render()
+                    .into_string();
+                let _ = SendMessage::quick_html(Cow::Owned(chat_id.into()), text)
+                    .call_on(&self.telegram)
+                    .await?;
+            }
+            Command::Search { query: "" } => {
+                self.reply(chat_id, reply_parameters, "Usage: /search <query>").await?;
+            }
+            Command::Search { query } => {
+                self.on_search(query, chat_id, reply_parameters).await?;
+            }
+            Command::Manage => {
                 self.on_manage_subscriptions(chat_id).await?;
             }
+            Command::History { text: "" } => {
+                self.reply(chat_id, reply_parameters, "Usage: /history <text>").await?;
+            }
+            Command::History { text } => {
+                self.on_history(HistoryCommand::bound(text), 0, chat_id).await?;
+            }
+            Command::Blocked => {
+                self.on_blocked_sellers(chat_id).await?;
+            }
+            Command::Settings { args } => {
+                self.on_settings(args, chat_id, reply_parameters).await?;
+            }
+            Command::Invite { args } => {
+                self.on_invite(args, chat_id, reply_parameters).await?;
+            }
+            Command::Authorized => {
+                self.on_authorized(chat_id).await?;
+            }
+            Command::Revoke { args } => {
+                self.on_revoke(args, chat_id, reply_parameters).await?;
+            }
+            Command::Unknown { .. } => {
+                self.reply(chat_id, reply_parameters, "I am sorry, but I do not know this command")
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle the `/start` command with a payload.
+    #[instrument(skip_all)]
+    async fn on_payload(&self, payload: &str, chat_id: i64) -> Result {
+        let command = CommandPayload::from_base64(payload)?;
+        debug!(?command, "❕ Received command");
+
+        if command.manage.is_some() {
+            self.on_manage_subscriptions(chat_id).await?;
+        }
+
+        if let Some(seller_command) = command.seller {
+            self.on_seller_command(seller_command, chat_id).await?;
+        }
+
+        if let Some(history_command) = command.history {
+            self.on_history(&history_command.text, history_command.offset, chat_id).await?;
+        }
 
-            if let Some(subscription_command) = command.subscription {
-                let query_hash = subscription_command.query_hash;
-                let subscription = Subscription { query_hash, chat_id };
-                let connection = &mut *self.db.connection().await;
+        if let Some(subscription_command) = command.subscription {
+            let command_builder = self.command_builder.for_chat(chat_id);
+            let query_hash = subscription_command.query_hash;
+            let subscription = Subscription { query_hash, chat_id };
+            let markup = {
+                let connection = &mut *self.db.connection().await?;
                 let query_text = SearchQueries(connection).fetch_text(query_hash).await?;
-                let mut subscriptions = Subscriptions(connection);
 
                 match SubscriptionAction::try_from(subscription_command.action) {
                     Ok(SubscriptionAction::Subscribe) => {
-                        info!(subscription.query_hash, "➕ Subscribing");
-                        subscriptions.upsert(subscription).await?;
-                        let unsubscribe_link =
-                            self.command_builder.unsubscribe_link(subscription.query_hash);
-                        let markup = html! {
-                            "You are now subscribed"
-                            (DELIMITER)
-                            (ManageSearchQuery::new(&query_text, &[&unsubscribe_link, &self.command_builder.manage_link()]))
-                        };
-                        let send_message = SendMessage::quick_html(
-                            Cow::Owned(chat_id.into()),
-                            markup.render().into_string(),
-                        );
-                        let _ = send_message.call_on(&self.telegram).await?;
+                        let quotas = self.reloadable.borrow().quotas;
+                        if !Subscriptions(connection).exists(subscription).await?
+                            && !quotas.allows_subscription(Usage::fetch(connection, chat_id).await?)
+                        {
+                            warn!(chat_id, "⚠️ Too many subscriptions");
+                            html! {
+                                "😔 Sorry, you have reached the limit of subscriptions, unsubscribe from something first"
+                                (DELIMITER)
+                                (command_builder.manage_link())
+                            }
+                        } else {
+                            info!(subscription.query_hash, "➕ Subscribing");
+                            Subscriptions(connection).upsert(subscription).await?;
+                            let unsubscribe_link =
+                                command_builder.unsubscribe_link(subscription.query_hash);
+                            html! {
+                                "You are now subscribed"
+                                (DELIMITER)
+                                (ManageSearchQuery::new(&query_text, &[&unsubscribe_link, &command_builder.manage_link()]))
+                            }
+                        }
                     }
 
                     Ok(SubscriptionAction::Unsubscribe) => {
                         info!(subscription.query_hash, "➖ Unsubscribing");
-                        subscriptions.delete(subscription).await?;
+                        Subscriptions(connection).delete(subscription).await?;
                         let resubscribe_link =
-                            self.command_builder.resubscribe_link(subscription.query_hash);
-                        let markup = html! {
+                            command_builder.resubscribe_link(subscription.query_hash);
+                        html! {
                             "You are now unsubscribed"
                             (DELIMITER)
-                            (ManageSearchQuery::new(&query_text, &[&resubscribe_link, &self.command_builder.manage_link()]))
-                        };
-                        let send_message = SendMessage::quick_html(
-                            Cow::Owned(chat_id.into()),
-                            markup.render().into_string(),
-                        );
-                        let _ = send_message.call_on(&self.telegram).await?;
+                            (ManageSearchQuery::new(&query_text, &[&resubscribe_link, &command_builder.manage_link()]))
+                        }
                     }
 
-                    _ => {} // TODO: technically, I should return a message that the action is no longer supported
+                    _ => return Ok(()), // TODO: technically, I should return a message that the action is no longer supported
                 }
+            };
+            SendMessage::quick_html(Cow::Owned(chat_id.into()), markup.render().into_string())
+                .call_and_discard_on(&self.telegram)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Block or unblock the seller.
+    #[instrument(skip_all, fields(marketplace_id = command.marketplace_id, seller_id = command.seller_id))]
+    async fn on_seller_command(&self, command: SellerCommand, chat_id: i64) -> Result {
+        let command_builder = self.command_builder.for_chat(chat_id);
+        let markup = match SellerAction::try_from(command.action) {
+            Ok(SellerAction::Block) => {
+                info!(chat_id, "🚫 Blocking the seller");
+                let blocked_seller = BlockedSeller {
+                    chat_id,
+                    marketplace_id: command.marketplace_id,
+                    seller_id: command.seller_id,
+                    blocked_at: Utc::now(),
+                };
+                BlockedSellers(&mut *self.db.connection().await?).upsert(&blocked_seller).await?;
+                html! {
+                    "🚫 The seller is blocked, you will not see their items anymore"
+                    @if let Some(link) = command_builder.unblock_seller_link(&blocked_seller.marketplace_id, &blocked_seller.seller_id) {
+                        (DELIMITER)
+                        (link)
+                    }
+                }
+            }
+            Ok(SellerAction::Unblock) => {
+                info!(chat_id, "✅ Unblocking the seller");
+                BlockedSellers(&mut *self.db.connection().await?)
+                    .delete(chat_id, &command.marketplace_id, &command.seller_id)
+                    .await?;
+                html! {
+                    "✅ The seller is unblocked"
+                    @if let Some(link) = command_builder.block_seller_link(&command.marketplace_id, &command.seller_id) {
+                        (DELIMITER)
+                        (link)
+                    }
+                }
+            }
+            _ => return Ok(()),
+        };
+        SendMessage::quick_html(Cow::Owned(chat_id.into()), markup.render().into_string())
+            .call_and_discard_on(&self.telegram)
+            .await
+    }
+
+    /// Search the items notified to the chat and send the page of results.
+    ///
+    /// The text is expected to be [bound][HistoryCommand::bound] already.
+    #[instrument(skip_all, fields(text = text, offset = offset))]
+    async fn on_history(&self, text: &str, offset: u32, chat_id: i64) -> Result {
+        // Fetch one more item to know whether there is the next page.
+        let mut items = Items(&mut *self.db.connection().await?)
+            .search_notified(chat_id, text, HISTORY_PAGE_SIZE + 1, offset)
+            .await?;
+        info!(chat_id, n_items = items.len(), "🔎 Searched the history");
+        let next_page_link = (items.len() > HISTORY_PAGE_SIZE as usize).then(|| {
+            self.command_builder.for_chat(chat_id).history_link(text, offset + HISTORY_PAGE_SIZE)
+        });
+        items.truncate(HISTORY_PAGE_SIZE as usize);
+        let markup = render::history(&items, next_page_link.as_ref());
+        SendMessage::quick_html(Cow::Owned(chat_id.into()), markup.render().into_string())
+            .call_and_discard_on(&self.telegram)
+            .await
+    }
+
+    /// List the chat's blocked sellers.
+    #[instrument(skip_all)]
+    async fn on_blocked_sellers(&self, chat_id: i64) -> Result {
+        let sellers =
+            BlockedSellers(&mut *self.db.connection().await?).fetch_all_of(chat_id).await?;
+        let markup = render::blocked_sellers(&sellers, &self.command_builder.for_chat(chat_id));
+        SendMessage::quick_html(Cow::Owned(chat_id.into()), markup.render().into_string())
+            .call_and_discard_on(&self.telegram)
+            .await
+    }
+
+    async fn is_authorized(&self, chat_id: i64) -> Result<bool> {
+        if self.reloadable.borrow().is_authorized(chat_id) {
+            return Ok(true);
+        }
+        AuthorizedChats(&mut *self.db.connection().await?).exists(chat_id).await
+    }
+
+    /// Try to redeem the invite from an unauthorized chat.
+    ///
+    /// # Returns
+    ///
+    /// Whether the payload contained an invite.
+    #[instrument(skip_all)]
+    async fn on_invite_payload(&self, payload: &str, chat_id: i64) -> Result<bool> {
+        let Some(invite) = CommandPayload::from_base64(payload).ok().and_then(|it| it.invite)
+        else {
+            return Ok(false);
+        };
+        let text = {
+            // Commit in either case, so that the expired invite gets deleted too.
+            let mut transaction = self.db.begin().await?;
+            let text = if Invites(&mut transaction).redeem(&invite.token, Utc::now()).await? {
+                info!(chat_id, "🎟️ Redeemed the invite");
+                let authorized_chat = AuthorizedChat { chat_id, authorized_at: Utc::now() };
+                AuthorizedChats(&mut transaction).upsert(&authorized_chat).await?;
+                "✅ Welcome! Just send me a search query to start"
+            } else {
+                warn!(chat_id, "⚠️ Invalid or expired invite");
+                "⛔️ The invite is invalid or has expired"
+            };
+            transaction.commit().await.context("failed to commit the invite redemption")?;
+            text
+        };
+        SendMessage::builder()
+            .chat_id(Cow::Owned(chat_id.into()))
+            .text(text)
+            .build()
+            .call_and_discard_on(&self.telegram)
+            .await?;
+        Ok(true)
+    }
+
+    /// Generate an invite.
+    ///
+    /// Without arguments, the invite is one-time and expires in a week.
+    /// With a number of hours, the invite is reusable until it expires.
+    #[instrument(skip_all)]
+    async fn on_invite(
+        &self,
+        args: &str,
+        chat_id: i64,
+        reply_parameters: ReplyParameters,
+    ) -> Result {
+        let invite = if args.is_empty() {
+            Invite::generate(Utc::now() + TimeDelta::weeks(1), false)
+        } else if let Ok(hours) = args.parse::<u16>() {
+            Invite::generate(Utc::now() + TimeDelta::hours(hours.into()), true)
+        } else {
+            return self.reply(chat_id, reply_parameters, "Usage: /invite [<hours>]").await;
+        };
+        Invites(&mut *self.db.connection().await?).insert(&invite).await?;
+        info!(?invite.expires_at, invite.is_reusable, "🎟️ Generated an invite");
+        let private_link =
+            self.command_builder.for_group(false).invite_link("Private chat", invite.token.clone());
+        let group_link = self.command_builder.for_group(true).invite_link("Group", invite.token);
+        let text =
+            render::invite(&private_link, &group_link, invite.expires_at, invite.is_reusable)
+                .render()
+                .into_string();
+        SendMessage::quick_html(Cow::Owned(chat_id.into()), text)
+            .call_and_discard_on(&self.telegram)
+            .await
+    }
+
+    /// List the authorized chats.
+    #[instrument(skip_all)]
+    async fn on_authorized(&self, chat_id: i64) -> Result {
+        let authorized_chats =
+            AuthorizedChats(&mut *self.db.connection().await?).fetch_all().await?;
+        let mut static_chat_ids: Vec<i64> = {
+            let reloadable = self.reloadable.borrow();
+            reloadable.authorized_chat_ids.union(&reloadable.admin_chat_ids).copied().collect()
+        };
+        static_chat_ids.sort_unstable();
+        let text =
+            render::authorized_chats(&static_chat_ids, &authorized_chats).render().into_string();
+        SendMessage::quick_html(Cow::Owned(chat_id.into()), text)
+            .call_and_discard_on(&self.telegram)
+            .await
+    }
+
+    /// Revoke the chat authorization and delete its subscriptions.
+    #[instrument(skip_all)]
+    async fn on_revoke(
+        &self,
+        args: &str,
+        chat_id: i64,
+        reply_parameters: ReplyParameters,
+    ) -> Result {
+        let Ok(revoked_chat_id) = args.parse::<i64>() else {
+            return self.reply(chat_id, reply_parameters, "Usage: /revoke <chat ID>").await;
+        };
+        if self.reloadable.borrow().is_authorized(revoked_chat_id) {
+            return self
+                .reply(
+                    chat_id,
+                    reply_parameters,
+                    "The chat is authorized in the configuration, update it instead",
+                )
+                .await;
+        }
+        let is_revoked = {
+            let mut transaction = self.db.begin().await?;
+            let is_revoked = AuthorizedChats(&mut transaction).delete(revoked_chat_id).await?;
+            if is_revoked {
+                Subscriptions(&mut transaction).delete_all_of(revoked_chat_id).await?;
             }
+            transaction.commit().await.context("failed to commit the revocation")?;
+            is_revoked
+        };
+        if is_revoked {
+            info!(revoked_chat_id, "🚷 Revoked the authorization");
+            self.reply(chat_id, reply_parameters, "✅ Revoked, the subscriptions are deleted").await
         } else {
-            // Unknown command.
-            let _ = SendMessage::builder()
-                .chat_id(Cow::Owned(chat_id.into()))
-                .text("I am sorry, but I do not know this command")
-                .reply_parameters(reply_parameters)
-                .build()
-                .call_on(&self.telegram)
-                .await?;
+            self.reply(chat_id, reply_parameters, "The chat is not authorized with an invite").await
         }
+    }
+
+    /// Show or change the chat settings.
+    #[instrument(skip_all)]
+    async fn on_settings(
+        &self,
+        args: &str,
+        chat_id: i64,
+        reply_parameters: ReplyParameters,
+    ) -> Result {
+        let mut chat = Chats(&mut *self.db.connection().await?).fetch(chat_id).await?;
+        let args: Vec<&str> = args.split_whitespace().collect();
+        match args.as_slice() {
+            [] => {}
+            ["paused", value] => {
+                let Some(is_paused) = parse_switch(value) else {
+                    return self.reply(chat_id, reply_parameters, "Use either on or off").await;
+                };
+                info!(chat_id, is_paused, "⚙️ Updating the settings");
+                chat.is_paused = is_paused;
+                Chats(&mut *self.db.connection().await?).upsert(&chat).await?;
+            }
+            ["album", value] => {
+                let Some(sends_album) = parse_switch(value) else {
+                    return self.reply(chat_id, reply_parameters, "Use either on or off").await;
+                };
+                info!(chat_id, sends_album, "⚙️ Updating the settings");
+                chat.sends_album = sends_album;
+                Chats(&mut *self.db.connection().await?).upsert(&chat).await?;
+            }
+            _ => {
+                return self.reply(chat_id, reply_parameters, "I do not know this setting").await;
+            }
+        }
+        let text = render::settings(&chat).render().into_string();
+        let _ = SendMessage::quick_html(Cow::Owned(chat_id.into()), text)
+            .call_on(&self.telegram)
+            .await?;
         Ok(())
     }
 
+    /// Reply with a plain text message.
+    async fn reply(
+        &self,
+        chat_id: i64,
+        reply_parameters: ReplyParameters,
+        text: impl Into<Cow<'static, str>>,
+    ) -> Result {
+        SendMessage::builder()
+            .chat_id(Cow::Owned(chat_id.into()))
+            .text(text)
+            .reply_parameters(reply_parameters)
+            .build()
+            .call_and_discard_on(&self.telegram)
+            .await
+    }
+
     /// List the user's subscriptions.
     #[instrument(skip_all)]
     async fn on_manage_subscriptions(&self, chat_id: i64) -> Result {
         let subscriptions = self.db.subscriptions_of(chat_id).await?;
+        let usage = Usage::fetch(&mut *self.db.connection().await?, chat_id).await?;
+        let command_builder = self.command_builder.for_chat(chat_id);
         let markup = html! {
             @if subscriptions.is_empty() {
                 "You do not have any subscriptions at the moment"
             } @else {
                 "Here are your subscriptions:\n"
                 @for (subscription, search_query) in subscriptions {
-                    @let unsubscribe_link = self.command_builder.unsubscribe_link(subscription.query_hash);;
+                    @let unsubscribe_link = command_builder.unsubscribe_link(subscription.query_hash);;
                     "\n"
                     (ManageSearchQuery::new(&search_query.text, &[&unsubscribe_link]))
                 }
             }
+            "\n\n"
+            (render::usage(&self.reloadable.borrow().quotas, usage))
         };
         let _ = SendMessage::builder()
             .chat_id(Cow::Owned(chat_id.into()))
@@ -354,3 +850,9 @@ impl Bot {
         Ok(())
     }
 }
+
+/// Incoming request to the bot.
+enum Request<'a> {
+    Command(Command<'a>),
+    Search(Cow<'a, str>),
+}
@@ -6,29 +6,72 @@ use url::Url;
 
 use crate::{prelude::*, telegram::render::CommandLink};
 
+/// Maximum length of the deep linking payload.
+const MAX_PAYLOAD_LEN: usize = 64;
+
 /// Builder of `/start` commands with [deep linking][1].
 ///
 /// [1]: https://core.telegram.org/bots/features#deep-linking
 #[derive(Clone)]
 #[must_use]
-pub struct CommandBuilder(Url);
+pub struct CommandBuilder {
+    base_url: Url,
+
+    /// Deep linking parameter name: `start` for private chats and `startgroup` for groups.
+    parameter: &'static str,
+}
 
 impl CommandBuilder {
     pub fn new(me: &str) -> Result<Self> {
         let mut base_url = Url::parse("https://t.me/")?;
         base_url.set_path(me);
-        Ok(Self(base_url))
+        Ok(Self { base_url, parameter: "start" })
+    }
+
+    /// Return the builder which produces links for the specified chat.
+    ///
+    /// Negative chat IDs belong to groups. There, the links must use `startgroup`,
+    /// so that the command is sent to the group instead of the private chat with the bot.
+    pub fn for_chat(&self, chat_id: i64) -> Self {
+        self.for_group(chat_id < 0)
+    }
+
+    /// Return the builder which produces links either for groups, or for private chats.
+    pub fn for_group(&self, is_group: bool) -> Self {
+        let parameter = if is_group { "startgroup" } else { "start" };
+        Self { base_url: self.base_url.clone(), parameter }
     }
 
     /// Return the command builder base URL.
     pub const fn url(&self) -> &Url {
-        &self.0
+        &self.base_url
+    }
+
+    /// Return the bot's username.
+    pub fn username(&self) -> &str {
+        self.base_url.path().trim_start_matches('/')
     }
 
     /// Build a new command link.
     pub fn command_link(&self, content: &'static str, payload: &CommandPayload) -> CommandLink {
-        let mut url = self.0.clone();
-        url.query_pairs_mut().append_pair("start", &payload.to_base64());
+        self.link_to(content, &payload.to_base64())
+    }
+
+    /// Build a new command link, unless the payload exceeds the Telegram limit.
+    ///
+    /// For the payloads of variable length, which cannot be guaranteed to fit.
+    pub fn try_command_link(
+        &self,
+        content: &'static str,
+        payload: &CommandPayload,
+    ) -> Option<CommandLink> {
+        let payload = payload.to_base64();
+        (payload.len() <= MAX_PAYLOAD_LEN).then(|| self.link_to(content, &payload))
+    }
+
+    fn link_to(&self, content: &'static str, payload: &str) -> CommandLink {
+        let mut url = self.base_url.clone();
+        url.query_pairs_mut().append_pair(self.parameter, payload);
         CommandLink { content, url }
     }
 
@@ -42,11 +85,43 @@ impl CommandBuilder {
         self.command_link("Subscribe", &CommandPayload::subscribe_to(to_query_hash))
     }
 
+    /// Produce a «Follow seller» link, which subscribes to the seller's query.
+    pub fn follow_seller_link(&self, to_query_hash: i64) -> CommandLink {
+        self.command_link("Follow seller", &CommandPayload::subscribe_to(to_query_hash))
+    }
+
+    /// Produce a «Block seller» link, unless the seller ID is too long for the payload.
+    pub fn block_seller_link(&self, marketplace_id: &str, seller_id: &str) -> Option<CommandLink> {
+        self.try_command_link(
+            "Block seller",
+            &CommandPayload::block_seller(marketplace_id, seller_id),
+        )
+    }
+
+    /// Produce an «Unblock» link, unless the seller ID is too long for the payload.
+    pub fn unblock_seller_link(
+        &self,
+        marketplace_id: &str,
+        seller_id: &str,
+    ) -> Option<CommandLink> {
+        self.try_command_link("Unblock", &CommandPayload::unblock_seller(marketplace_id, seller_id))
+    }
+
+    /// Produce a «More» link to the next page of the history search.
+    pub fn history_link(&self, text: &str, offset: u32) -> CommandLink {
+        self.command_link("More", &CommandPayload::history(text, offset))
+    }
+
     /// Produce a standard «Re-subscribe» link.
     pub fn resubscribe_link(&self, to_query_hash: i64) -> CommandLink {
         self.command_link("Re-subscribe", &CommandPayload::subscribe_to(to_query_hash))
     }
 
+    /// Produce an invite link.
+    pub fn invite_link(&self, content: &'static str, token: Vec<u8>) -> CommandLink {
+        self.command_link(content, &CommandPayload::redeem_invite(token))
+    }
+
     /// Produce a standard «Unsubscribe» link.
     pub fn unsubscribe_link(&self, from_query_hash: i64) -> CommandLink {
         self.command_link("Unsubscribe", &CommandPayload::unsubscribe_from(from_query_hash))
@@ -63,6 +138,15 @@ pub struct CommandPayload {
 
     #[prost(tag = "4", message, optional)]
     pub manage: Option<ManageCommand>,
+
+    #[prost(tag = "5", message, optional)]
+    pub invite: Option<InviteCommand>,
+
+    #[prost(tag = "6", message, optional)]
+    pub seller: Option<SellerCommand>,
+
+    #[prost(tag = "7", message, optional)]
+    pub history: Option<HistoryCommand>,
 }
 
 impl CommandPayload {
@@ -76,15 +160,130 @@ impl CommandPayload {
     }
 
     pub const fn manage() -> Self {
-        Self { subscription: None, manage: Some(ManageCommand {}) }
+        Self {
+            subscription: None,
+            manage: Some(ManageCommand {}),
+            invite: None,
+            seller: None,
+            history: None,
+        }
     }
 
     pub const fn subscribe_to(query_hash: i64) -> Self {
-        Self { subscription: Some(SubscriptionCommand::subscribe_to(query_hash)), manage: None }
+        Self {
+            subscription: Some(SubscriptionCommand::subscribe_to(query_hash)),
+            manage: None,
+            invite: None,
+            seller: None,
+            history: None,
+        }
     }
 
     pub const fn unsubscribe_from(query_hash: i64) -> Self {
-        Self { subscription: Some(SubscriptionCommand::unsubscribe_from(query_hash)), manage: None }
+        Self {
+            subscription: Some(SubscriptionCommand::unsubscribe_from(query_hash)),
+            manage: None,
+            invite: None,
+            seller: None,
+            history: None,
+        }
+    }
+
+    pub fn block_seller(marketplace_id: &str, seller_id: &str) -> Self {
+        Self::seller(SellerCommand::new(marketplace_id, seller_id, SellerAction::Block))
+    }
+
+    pub fn unblock_seller(marketplace_id: &str, seller_id: &str) -> Self {
+        Self::seller(SellerCommand::new(marketplace_id, seller_id, SellerAction::Unblock))
+    }
+
+    const fn seller(command: SellerCommand) -> Self {
+        Self {
+            subscription: None,
+            manage: None,
+            invite: None,
+            seller: Some(command),
+            history: None,
+        }
+    }
+
+    pub fn history(text: &str, offset: u32) -> Self {
+        Self {
+            subscription: None,
+            manage: None,
+            invite: None,
+            seller: None,
+            history: Some(HistoryCommand { text: HistoryCommand::bound(text).to_string(), offset }),
+        }
+    }
+
+    pub const fn redeem_invite(token: Vec<u8>) -> Self {
+        Self {
+            subscription: None,
+            manage: None,
+            invite: Some(InviteCommand { token }),
+            seller: None,
+            history: None,
+        }
+    }
+}
+
+/// Block or unblock the seller in the chat.
+#[derive(Eq, PartialEq, Message)]
+pub struct SellerCommand {
+    #[prost(tag = "1", string)]
+    pub marketplace_id: String,
+
+    /// See [`crate::marketplace::item::Seller::key`].
+    #[prost(tag = "2", string)]
+    pub seller_id: String,
+
+    #[prost(tag = "3", enumeration = "SellerAction")]
+    pub action: i32,
+}
+
+impl SellerCommand {
+    fn new(marketplace_id: &str, seller_id: &str, action: SellerAction) -> Self {
+        Self {
+            marketplace_id: marketplace_id.to_string(),
+            seller_id: seller_id.to_string(),
+            action: action as i32,
+        }
+    }
+}
+
+#[derive(Debug, Enumeration)]
+#[repr(i32)]
+pub enum SellerAction {
+    None = 0,
+    Block = 1,
+    Unblock = 2,
+}
+
+/// Show the next page of the chat's notification history.
+#[derive(Eq, PartialEq, Message)]
+pub struct HistoryCommand {
+    /// Search text, see [`HistoryCommand::bound`].
+    #[prost(tag = "3", string)]
+    pub text: String,
+
+    #[prost(tag = "2", uint32)]
+    pub offset: u32,
+}
+
+impl HistoryCommand {
+    /// Maximum length of the search text in bytes, so that the payload fits in the Telegram limit.
+    pub const MAX_TEXT_LEN: usize = 40;
+
+    /// Cut the search text to [`Self::MAX_TEXT_LEN`], so that all the pages search for the same text.
+    pub fn bound(text: &str) -> &str {
+        let end = text
+            .char_indices()
+            .map(|(index, char)| index + char.len_utf8())
+            .take_while(|end| *end <= Self::MAX_TEXT_LEN)
+            .last()
+            .unwrap_or_default();
+        text[..end].trim_end()
     }
 }
 
@@ -92,6 +291,13 @@ impl CommandPayload {
 #[derive(Message)]
 pub struct ManageCommand {}
 
+/// Redeem the invite and authorize the chat.
+#[derive(Eq, PartialEq, Message)]
+pub struct InviteCommand {
+    #[prost(tag = "1", bytes)]
+    pub token: Vec<u8>,
+}
+
 #[derive(Eq, PartialEq, Message)]
 pub struct SubscriptionCommand {
     #[prost(tag = "1", sfixed64)]
@@ -140,6 +346,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_group_subscribe_link_ok() -> Result {
+        let search_query = SearchQuery::from("unifi");
+        let link =
+            CommandBuilder::new("mrktpltsbot")?.for_chat(-42).subscribe_link(search_query.hash);
+
+        // language=html
+        assert_eq!(
+            link.render().into_string(),
+            r#"<a href="https://t.me/mrktpltsbot?startgroup=GgsJ_5xfEFkYbu0QAQ">Subscribe</a>"#,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_username_ok() -> Result {
+        assert_eq!(CommandBuilder::new("mrktpltsbot")?.username(), "mrktpltsbot");
+        Ok(())
+    }
+
     #[test]
     fn test_deserialize_payload_ok() -> Result {
         let payload = CommandPayload::from_base64("GgsJ_5xfEFkYbu0QAQ")?;
@@ -149,4 +376,67 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_block_seller_payload_ok() -> Result {
+        let payload = CommandPayload::block_seller("kleinanzeigen", "12345678").to_base64();
+        assert!(payload.len() <= MAX_PAYLOAD_LEN, "Telegram limits the payload to 64 characters");
+        let payload = CommandPayload::from_base64(&payload)?;
+        assert_eq!(
+            payload.seller,
+            Some(SellerCommand::new("kleinanzeigen", "12345678", SellerAction::Block)),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_seller_link_with_long_username_omitted() -> Result {
+        let command_builder = CommandBuilder::new("mrktpltsbot")?;
+        assert!(command_builder.block_seller_link("vinted", "anna_vintage_wardrobe").is_some());
+        assert!(
+            command_builder
+                .block_seller_link(
+                    "kleinanzeigen",
+                    "Kleinanzeigen Nutzer mit einem sehr langen Namen"
+                )
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invite_payload_roundtrip_ok() -> Result {
+        let payload = CommandPayload::redeem_invite(vec![42; 16]);
+        let payload = CommandPayload::from_base64(&payload.to_base64())?;
+        assert_eq!(payload.invite, Some(InviteCommand { token: vec![42; 16] }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_payload_roundtrip_ok() -> Result {
+        let payload = CommandPayload::history("unifi u6", 100).to_base64();
+        let payload = CommandPayload::from_base64(&payload)?;
+        assert_eq!(
+            payload.history,
+            Some(HistoryCommand { text: "unifi u6".to_string(), offset: 100 }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_history_payload_ok() -> Result {
+        let text = "ubiquiti unifi cloud gateway ultra ucg-ultra router";
+        let payload = CommandPayload::history(text, 1_000_000).to_base64();
+        assert!(payload.len() <= MAX_PAYLOAD_LEN, "Telegram limits the payload to 64 characters");
+        let payload = CommandPayload::from_base64(&payload)?;
+        assert_eq!(payload.history.unwrap().text, "ubiquiti unifi cloud gateway ultra ucg-u");
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_bound_ok() {
+        assert_eq!(HistoryCommand::bound("unifi"), "unifi");
+        assert_eq!(HistoryCommand::bound(&"ü".repeat(30)), "ü".repeat(20));
+        assert_eq!(HistoryCommand::bound(&format!("{} unifi", "a".repeat(39))), "a".repeat(39));
+    }
 }
@@ -1,6 +1,6 @@
 use std::{borrow::Cow, fmt::Debug, time::Duration};
 
-use bon::{Builder, builder};
+use bon::Builder;
 use serde::{
     Serialize,
     de::{DeserializeOwned, IgnoredAny},
@@ -10,7 +10,20 @@ use crate::{
     client,
     prelude::*,
     serde::as_inner_json,
-    telegram::{Telegram, objects::*},
+    telegram::{
+        Telegram,
+        objects::{
+            BotCommand,
+            ChatId,
+            LinkPreviewOptions,
+            Media,
+            Message,
+            ParseMode,
+            ReplyParameters,
+            Update,
+            User,
+        },
+    },
 };
 
 /// [Telegram bot API][1] method.
@@ -118,6 +131,52 @@ impl Method for GetUpdates<'_> {
     }
 }
 
+/// Use this method to specify a URL and receive incoming updates via an outgoing [webhook][1].
+///
+/// [1]: https://core.telegram.org/bots/api#setwebhook
+#[derive(Builder, Serialize)]
+#[must_use]
+pub struct SetWebhook<'a> {
+    /// HTTPS URL to send updates to.
+    pub url: &'a str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_updates: Option<&'a [AllowedUpdate]>,
+
+    /// A secret token to be sent in a header `X-Telegram-Bot-Api-Secret-Token` in every webhook request.
+    ///
+    /// 1-256 characters. Only characters `A-Z`, `a-z`, `0-9`, `_` and `-` are allowed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_token: Option<&'a str>,
+}
+
+impl Method for SetWebhook<'_> {
+    type Response = bool;
+
+    fn name(&self) -> &'static str {
+        "setWebhook"
+    }
+}
+
+/// Use this method to [remove webhook integration][1] if you decide to switch back to `getUpdates`.
+///
+/// [1]: https://core.telegram.org/bots/api#deletewebhook
+#[derive(Builder, Serialize)]
+#[must_use]
+pub struct DeleteWebhook {
+    /// Pass `true` to drop all pending updates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_pending_updates: Option<bool>,
+}
+
+impl Method for DeleteWebhook {
+    type Response = bool;
+
+    fn name(&self) -> &'static str {
+        "deleteWebhook"
+    }
+}
+
 /// [Send a message][1].
 ///
 /// [1]: https://core.telegram.org/bots/api#sendmessage
@@ -192,6 +251,32 @@ impl Method for SendPhoto<'_> {
     }
 }
 
+/// [Send a group of photos][1] as an album.
+///
+/// [1]: https://core.telegram.org/bots/api#sendmediagroup
+#[derive(Builder, Serialize)]
+#[must_use]
+pub struct SendMediaGroup<'a> {
+    pub chat_id: Cow<'a, ChatId>,
+
+    /// A JSON-serialized array describing messages to be sent, must include 2-10 items.
+    ///
+    /// The caption of the first item is shown as the album caption.
+    #[serde(serialize_with = "as_inner_json")]
+    pub media: Vec<Media<'a>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_parameters: Option<ReplyParameters>,
+}
+
+impl Method for SendMediaGroup<'_> {
+    type Response = Vec<Message>;
+
+    fn name(&self) -> &'static str {
+        "sendMediaGroup"
+    }
+}
+
 /// Use this method to [change the list of the bot's commands].
 ///
 /// See [this manual][2] for more details about bot commands. Returns [`true`] on success.
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, iter::once};
 
 use bon::bon;
 use serde::Serialize;
@@ -8,11 +8,20 @@ use crate::{
     prelude::*,
     telegram::{
         Telegram,
-        methods::{Method, SendMessage, SendPhoto},
-        objects::{ChatId, LinkPreviewOptions, ParseMode, ReplyParameters},
+        methods::{Method, SendMediaGroup, SendMessage, SendPhoto},
+        objects::{ChatId, InputMediaPhoto, LinkPreviewOptions, Media, ParseMode, ReplyParameters},
     },
 };
 
+/// Maximum number of photos in a media group.
+const MAX_MEDIA_GROUP_SIZE: usize = 10;
+
+/// Maximum length of a photo caption.
+const MAX_CAPTION_LENGTH: usize = 1024;
+
+/// Maximum length of a text message.
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
 /// Reaction method on Telegram.
 #[derive(Serialize)]
 #[serde(untagged)]
@@ -20,6 +29,7 @@ use crate::{
 pub enum Notification<'a> {
     Message(SendMessage<'a>),
     Photo(SendPhoto<'a>),
+    MediaGroup(SendMediaGroup<'a>),
 }
 
 #[bon]
@@ -30,12 +40,12 @@ impl<'a> Notification<'a> {
         chat_id: Cow<'a, ChatId>,
         text: Cow<'a, str>,
         parse_mode: ParseMode,
-        picture_url: Option<&'a Url>,
+        #[builder(default)] picture_urls: &'a [Url],
         reply_parameters: Option<ReplyParameters>,
     ) -> Self {
         // Specific representation depends on how many pictures there are.
-        match picture_url {
-            None => Self::Message(
+        match picture_urls {
+            [] => Self::Message(
                 SendMessage::builder()
                     .chat_id(chat_id)
                     .text(text)
@@ -45,7 +55,7 @@ impl<'a> Notification<'a> {
                     .build(),
             ),
 
-            Some(url) => Self::Photo(
+            [url] => Self::Photo(
                 SendPhoto::builder()
                     .chat_id(chat_id)
                     .photo(url.as_str())
@@ -54,15 +64,65 @@ impl<'a> Notification<'a> {
                     .maybe_reply_parameters(reply_parameters)
                     .build(),
             ),
+
+            [first_url, rest_urls @ ..] => {
+                let first = InputMediaPhoto::builder()
+                    .media(first_url.as_str())
+                    .caption(text)
+                    .parse_mode(parse_mode)
+                    .build();
+                let rest = rest_urls
+                    .iter()
+                    .take(MAX_MEDIA_GROUP_SIZE - 1)
+                    .map(|url| InputMediaPhoto::builder().media(url.as_str()).build());
+                Self::MediaGroup(
+                    SendMediaGroup::builder()
+                        .chat_id(chat_id)
+                        .media(once(first).chain(rest).map(Media::InputMediaPhoto).collect())
+                        .maybe_reply_parameters(reply_parameters)
+                        .build(),
+                )
+            }
         }
     }
 }
 
 impl Notification<'_> {
+    /// Maximum length of the text, which is sent either as a message or as a caption of the pictures.
+    pub const fn max_text_length(picture_urls: &[Url]) -> usize {
+        if picture_urls.is_empty() { MAX_MESSAGE_LENGTH } else { MAX_CAPTION_LENGTH }
+    }
+
     pub async fn react_to(&self, telegram: &Telegram) -> Result {
         match self {
             Notification::Message(inner) => inner.call_and_discard_on(telegram).await,
             Notification::Photo(inner) => inner.call_and_discard_on(telegram).await,
+            Notification::MediaGroup(inner) => inner.call_and_discard_on(telegram).await,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_group_ok() -> Result {
+        let picture_urls =
+            [Url::parse("https://example.com/1.jpg")?, Url::parse("https://example.com/2.jpg")?];
+        let notification = Notification::builder()
+            .chat_id(Cow::Owned(ChatId::Integer(42)))
+            .text("Caption".into())
+            .parse_mode(ParseMode::Html)
+            .picture_urls(&picture_urls)
+            .build();
+        assert_eq!(
+            serde_json::to_value(&notification)?,
+            serde_json::json!({
+                "chat_id": 42,
+                "media": r#"[{"type":"photo","media":"https://example.com/1.jpg","caption":"Caption","parse_mode":"HTML"},{"type":"photo","media":"https://example.com/2.jpg"}]"#,
+            }),
+        );
+        Ok(())
+    }
+}
@@ -110,6 +110,31 @@ impl Messages {
 #[must_use]
 pub struct Chat {
     pub id: ChatId,
+
+    #[serde(rename = "type", default)]
+    pub kind: ChatKind,
+}
+
+/// Type of [chat][1].
+///
+/// [1]: https://core.telegram.org/bots/api#chat
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatKind {
+    #[default]
+    Private,
+
+    Group,
+
+    Supergroup,
+
+    Channel,
+}
+
+impl ChatKind {
+    pub const fn is_private(self) -> bool {
+        matches!(self, Self::Private)
+    }
 }
 
 #[derive(Serialize)]
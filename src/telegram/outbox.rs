@@ -0,0 +1,228 @@
+//! Delivery of the item notifications from the outbox.
+
+use std::{borrow::Cow, time::Duration};
+
+use bon::Builder;
+use chrono::{TimeDelta, Utc};
+use tokio::time::sleep;
+
+use crate::{
+    db::{Db, Outbox, OutboxEntry},
+    prelude::*,
+    telegram::{Telegram, notification::Notification, objects::ParseMode, result::ApiError},
+};
+
+/// Maximum number of the delivery attempts per entry.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Sends the pending notifications from the outbox, retrying the failed ones.
+///
+/// Each notification is delivered at most once: an attempt interrupted by a crash is not retried,
+/// and neither is an attempt, which Telegram might have accepted.
+#[derive(Builder)]
+pub struct OutboxSender {
+    db: Db,
+    telegram: Telegram,
+
+    /// Interval between the outbox checks, when there is nothing to send.
+    #[builder(default = Duration::from_secs(1))]
+    poll_interval: Duration,
+}
+
+impl OutboxSender {
+    pub async fn run(self) {
+        info!("🔄 Running the outbox sender…");
+        match self.drop_in_flight().await {
+            Ok(0) => {}
+            Ok(n_dropped) => warn!(n_dropped, "⚠️ Dropped the interrupted notifications"),
+            Err(error) => error!("‼️ Failed to drop the interrupted notifications: {error:#}"),
+        }
+        loop {
+            match self.send_next().await {
+                Ok(true) => {}
+                Ok(false) => sleep(self.poll_interval).await,
+                Err(error) => {
+                    error!("‼️ Failed to handle the outbox: {error:#}");
+                    sleep(self.poll_interval).await;
+                }
+            }
+        }
+    }
+
+    async fn drop_in_flight(&self) -> Result<u64> {
+        Outbox(&mut *self.db.connection().await?).drop_in_flight().await
+    }
+
+    /// Send the next due notification.
+    ///
+    /// # Returns
+    ///
+    /// Whether there was a notification to send.
+    async fn send_next(&self) -> Result<bool> {
+        let entry = {
+            let connection = &mut *self.db.connection().await?;
+            let Some(mut entry) = Outbox(connection).next_due(Utc::now()).await? else {
+                return Ok(false);
+            };
+            Outbox(connection).start_attempt(&mut entry).await?;
+            entry
+        };
+        info!(entry.item_id, entry.chat_id, entry.n_attempts, "✉️ Sending the notification…");
+        let Err(error) = send(&self.telegram, &entry).await else {
+            Outbox(&mut *self.db.connection().await?).delete(&entry).await?;
+            return Ok(true);
+        };
+        match Failure::classify(&error) {
+            Failure::Retry(_) if entry.n_attempts >= MAX_ATTEMPTS => {
+                error!(entry.item_id, entry.chat_id, "‼️ Giving up on the notification: {error:#}");
+                Outbox(&mut *self.db.connection().await?).delete(&entry).await?;
+            }
+            Failure::Retry(retry_after) => {
+                let next_attempt_at =
+                    Utc::now() + retry_after.unwrap_or_else(|| retry_delay(entry.n_attempts));
+                warn!(
+                    entry.item_id,
+                    entry.chat_id,
+                    %next_attempt_at,
+                    "⚠️ Failed to send the notification: {error:#}",
+                );
+                Outbox(&mut *self.db.connection().await?)
+                    .reschedule(&entry, next_attempt_at)
+                    .await?;
+            }
+            Failure::MaybeDelivered => {
+                warn!(
+                    entry.item_id,
+                    entry.chat_id,
+                    "⚠️ The notification might have been delivered, not retrying: {error:#}",
+                );
+                Outbox(&mut *self.db.connection().await?).delete(&entry).await?;
+            }
+            Failure::Rejected => {
+                error!(
+                    entry.item_id,
+                    entry.chat_id, "‼️ Telegram rejected the notification: {error:#}"
+                );
+                Outbox(&mut *self.db.connection().await?).delete(&entry).await?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+async fn send(telegram: &Telegram, entry: &OutboxEntry) -> Result {
+    Notification::builder()
+        .chat_id(Cow::Owned(entry.chat_id.into()))
+        .text(Cow::Borrowed(&entry.text))
+        .picture_urls(&entry.picture_urls)
+        .parse_mode(ParseMode::Html)
+        .build()
+        .react_to(telegram)
+        .await
+}
+
+/// Outcome of a failed delivery attempt.
+#[derive(Debug, PartialEq, Eq)]
+enum Failure {
+    /// Telegram has provably not accepted the request, so it is safe to retry.
+    ///
+    /// Contains the delay, which Telegram asked for, if any.
+    Retry(Option<TimeDelta>),
+
+    /// Telegram might have delivered the message, so retrying could duplicate it.
+    MaybeDelivered,
+
+    /// Telegram has rejected the request, and repeating it would not help.
+    Rejected,
+}
+
+impl Failure {
+    fn classify(error: &Error) -> Self {
+        if let Some(error) = error.downcast_ref::<ApiError>() {
+            return match error.error_code {
+                429 => Self::Retry(error.retry_after.map(|secs| TimeDelta::seconds(secs.into()))),
+                400..=499 => Self::Rejected,
+                _ => Self::MaybeDelivered,
+            };
+        }
+        let is_connect = match error.downcast_ref::<reqwest_middleware::Error>() {
+            Some(reqwest_middleware::Error::Reqwest(error)) => error.is_connect(),
+            _ => error.downcast_ref::<reqwest::Error>().is_some_and(reqwest::Error::is_connect),
+        };
+        if is_connect { Self::Retry(None) } else { Self::MaybeDelivered }
+    }
+}
+
+/// Exponential delay before the next attempt: 10 seconds, 20 seconds, 40 seconds and so on.
+fn retry_delay(n_attempts: u32) -> TimeDelta {
+    TimeDelta::seconds(10 << n_attempts.saturating_sub(1).min(10))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn api_error(error_code: i32, retry_after: Option<u32>) -> Error {
+        ApiError { error_code, description: String::new(), retry_after }.into()
+    }
+
+    async fn get(url: &str, timeout: Duration) -> Error {
+        reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .build()
+            .get(url)
+            .timeout(timeout)
+            .send()
+            .await
+            .unwrap_err()
+            .into()
+    }
+
+    #[test]
+    fn classify_rate_limit_ok() {
+        assert_eq!(
+            Failure::classify(&api_error(429, Some(5))),
+            Failure::Retry(Some(TimeDelta::seconds(5))),
+        );
+    }
+
+    #[test]
+    fn classify_client_error_ok() {
+        assert_eq!(Failure::classify(&api_error(400, None)), Failure::Rejected);
+        assert_eq!(Failure::classify(&api_error(403, None)), Failure::Rejected);
+    }
+
+    #[test]
+    fn classify_server_error_ok() {
+        assert_eq!(Failure::classify(&api_error(502, None)), Failure::MaybeDelivered);
+    }
+
+    #[tokio::test]
+    async fn classify_connect_error_ok() -> Result {
+        // Grab a free port and release it, so that nothing listens on it:
+        let address = TcpListener::bind("127.0.0.1:0").await?.local_addr()?;
+        let error = get(&format!("http://{address}"), Duration::from_secs(5)).await;
+        assert_eq!(Failure::classify(&error), Failure::Retry(None));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn classify_timeout_ok() -> Result {
+        // The listener accepts the connection, but never responds:
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let error = get(&url, Duration::from_millis(100)).await;
+        assert_eq!(Failure::classify(&error), Failure::MaybeDelivered);
+        Ok(())
+    }
+
+    #[test]
+    fn retry_delay_ok() {
+        assert_eq!(retry_delay(1), TimeDelta::seconds(10));
+        assert_eq!(retry_delay(2), TimeDelta::seconds(20));
+        assert_eq!(retry_delay(4), TimeDelta::seconds(80));
+    }
+}
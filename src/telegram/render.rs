@@ -2,12 +2,29 @@
 
 use std::borrow::Cow;
 
+use chrono::{DateTime, Utc};
 use maud::{Markup, PreEscaped, Render, html};
+use scraper::Html;
 use url::Url;
 
 use crate::{
-    marketplace::item::{Amount, Condition, Delivery, GeoLocation, Item, Location, Price, Seller},
-    telegram::objects::ChatId,
+    db::{AuthorizedChat, BlockedSeller, Chat, NotifiedItem},
+    marketplace::item::{
+        Amount,
+        Condition,
+        Delivery,
+        GeoLocation,
+        Item,
+        Location,
+        Price,
+        Rating,
+        Seller,
+    },
+    quotas::{Quotas, Usage},
+    telegram::{
+        commands::CommandBuilder,
+        objects::{BotCommand, ChatId},
+    },
 };
 
 /// Just `<strong> • </strong>`.
@@ -33,34 +50,266 @@ pub fn unauthorized(chat_id: &ChatId) -> Markup {
     }
 }
 
-/// Render the item description.
-pub fn item_description(item: &Item, manage_search_query: &ManageSearchQuery<'_>) -> String {
-    let markup = html! {
-        strong { a href=(item.url) { (item.title) } }
-        "\n"
-        (manage_search_query)
+/// Render the `/help` message.
+pub fn help(
+    commands: &[BotCommand<'_>],
+    admin_commands: &[BotCommand<'_>],
+    marketplace_ids: &[&str],
+) -> Markup {
+    html! {
+        "Send me a search query to see what is available right now and subscribe to it."
+        " Prefix a word with " code { "-" } " to exclude items containing it, for example: "
+        code { "unifi -camera" }
         "\n\n"
-        (item.price)
-        @if let Some(condition) = item.condition {
-            (DELIMITER)
-            (condition)
+        "Add " code { "site:" } " to search only on the specified marketplaces, for example: "
+        code { "patagonia jacket site:vinted" }
+        ". Available marketplaces: "
+        @for (i, id) in marketplace_ids.iter().enumerate() {
+            @if i != 0 { ", " }
+            code { (id) }
+        }
+        "\n\n"
+        "Vinted also understands "
+        code { "brand:" } ", " code { "size:" } ", " code { "catalog:" } ", "
+        code { "color:" } ", " code { "status:" } " and " code { "price:" }
+        ", for example: "
+        code { "jacket brand:patagonia size:m status:very_good price:-80" }
+        ". These restrict the search to Vinted, except for " code { "price:" }
+        ", which Marktplaats and 2dehands understand too. Quote the values with spaces, for example: "
+        code { "brand:\"the north face\"" }
+        "\n\n"
+        "Use " strong { "Follow seller" } " on any item to get notified about everything the seller lists."
+        "\n\n"
+        "In a group, use " code { "/search" } " or mention me with the query instead."
+        "\n\n"
+        @for command in commands {
+            "/" (command.command) (DELIMITER) (command.description) "\n"
+        }
+        @if !admin_commands.is_empty() {
+            "\n"
+            strong { "Admin commands:" }
+            "\n"
+            @for command in admin_commands {
+                "/" (command.command) (DELIMITER) (command.description) "\n"
+            }
+        }
+    }
+}
+
+/// Render the newly generated invite.
+pub fn invite(
+    private_link: &CommandLink,
+    group_link: &CommandLink,
+    expires_at: DateTime<Utc>,
+    is_reusable: bool,
+) -> Markup {
+    html! {
+        "Send one of the links to the person you want to invite:"
+        "\n\n"
+        (private_link) (DELIMITER) (group_link)
+        "\n\n"
+        @if is_reusable { "The invite is reusable" } @else { "The invite may be used only once" }
+        ", and it expires at " code { (expires_at.format("%Y-%m-%d %H:%M UTC")) }
+    }
+}
+
+/// Render the list of authorized chats.
+pub fn authorized_chats(static_chat_ids: &[i64], authorized_chats: &[AuthorizedChat]) -> Markup {
+    html! {
+        strong { "Authorized in the configuration:" }
+        "\n"
+        @for chat_id in static_chat_ids {
+            code { (chat_id) } "\n"
+        }
+        "\n"
+        strong { "Authorized with invites:" }
+        "\n"
+        @if authorized_chats.is_empty() {
+            "None"
         }
-        @if let Some(delivery) = item.delivery {
+        @for chat in authorized_chats {
+            code { (chat.chat_id) }
+            " since "
+            (chat.authorized_at.format("%Y-%m-%d"))
             (DELIMITER)
-            (delivery)
+            code { "/revoke " (chat.chat_id) }
+            "\n"
+        }
+    }
+}
+
+/// Render the chat's blocked sellers.
+pub fn blocked_sellers(sellers: &[BlockedSeller], command_builder: &CommandBuilder) -> Markup {
+    html! {
+        @if sellers.is_empty() {
+            "You have not blocked any sellers. Use " strong { "Block seller" } " on an item to stop seeing the seller's items"
+        } @else {
+            "🚫 Blocked sellers:"
+            "\n\n"
+            @for seller in sellers {
+                code { (seller.marketplace_id) } " " code { (seller.seller_id) }
+                @if let Some(link) = command_builder.unblock_seller_link(&seller.marketplace_id, &seller.seller_id) {
+                    (DELIMITER)
+                    (link)
+                }
+                "\n"
+            }
         }
-        @if let Some(description) = &item.description {
+    }
+}
+
+/// Render a page of the history search results.
+pub fn history(items: &[NotifiedItem], next_page_link: Option<&CommandLink>) -> Markup {
+    html! {
+        @if items.is_empty() {
+            "Nothing found among the items you were notified about"
+        } @else {
+            "🔎 Found in your history:"
             "\n\n"
-            blockquote { (description) }
+            @for NotifiedItem { item, notified_at } in items {
+                a href=(item.url) { (item.title) }
+                @if let Some(price) = item.price() {
+                    (DELIMITER)
+                    (price)
+                }
+                (DELIMITER)
+                code { (item.marketplace_id) }
+                @if let Some(notified_at) = notified_at {
+                    (DELIMITER)
+                    "🕒 " (notified_at.format("%Y-%m-%d %H:%M UTC"))
+                }
+                "\n"
+            }
+            @if let Some(next_page_link) = next_page_link {
+                "\n"
+                (next_page_link)
+            }
         }
+    }
+}
+
+/// Render the chat settings.
+pub fn settings(chat: &Chat) -> Markup {
+    html! {
+        "Chat settings:"
         "\n\n"
-        (item.seller)
-        @if let Some(location) = &item.location {
-            (DELIMITER)
-            (location)
+        "⏸️ Notifications paused: " strong { (switch(chat.is_paused)) }
+        (DELIMITER)
+        code { "/settings paused on|off" }
+        "\n"
+        "🖼️ All photos as an album: " strong { (switch(chat.sends_album)) }
+        (DELIMITER)
+        code { "/settings album on|off" }
+    }
+}
+
+const fn switch(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+/// Render the chat's quota usage.
+pub fn usage(quotas: &Quotas, usage: Usage) -> Markup {
+    html! {
+        "📊 Subscriptions: " (usage.n_subscriptions)
+        @if let Some(max) = quotas.max_subscriptions { " / " (max) }
+        (DELIMITER)
+        "notifications in the last hour: " (usage.n_notifications_last_hour)
+        @if let Some(max) = quotas.max_notifications_per_hour { " / " (max) }
+    }
+}
+
+/// Render the item description.
+///
+/// The item's description gets truncated, so that the visible text fits in the maximum length.
+pub fn item_description(
+    item: &Item,
+    manage_search_query: &ManageSearchQuery<'_>,
+    seller_links: &[CommandLink],
+    duplicates: &[Item],
+    max_length: usize,
+) -> String {
+    let render = |description: Option<&str>| {
+        html! {
+            strong { a href=(item.url) { (item.title) } }
+            "\n"
+            (manage_search_query)
+            "\n\n"
+            (item.price)
+            @if let Some(condition) = item.condition {
+                (DELIMITER)
+                (condition)
+            }
+            @if let Some(delivery) = item.delivery {
+                (DELIMITER)
+                (delivery)
+            }
+            @if let Some(description) = description {
+                "\n\n"
+                blockquote { (description) }
+            }
+            "\n\n"
+            @if let Some(seller) = &item.seller {
+                (seller)
+                @if let Some(rating) = seller.rating {
+                    " " (rating)
+                }
+            }
+            @for (i, link) in seller_links.iter().enumerate() {
+                @if i != 0 || item.seller.is_some() { (DELIMITER) }
+                (link)
+            }
+            @if let Some(location) = &item.location {
+                @if item.seller.is_some() || !seller_links.is_empty() { (DELIMITER) }
+                (location)
+            }
+            @if let Some(posted_at) = item.posted_at {
+                (DELIMITER)
+                "🕒 " (posted_at.format("%Y-%m-%d %H:%M UTC"))
+            }
+            @if !duplicates.is_empty() {
+                "\n\n"
+                "🔁 Also listed on "
+                @for (i, duplicate) in duplicates.iter().enumerate() {
+                    @if i != 0 { ", " }
+                    a href=(duplicate.url) { (duplicate.marketplace_id) }
+                }
+            }
         }
+        .render()
+        .into_string()
     };
-    markup.render().into_string()
+    let Some(description) = item.description.as_deref() else {
+        return render(None);
+    };
+    let text = render(Some(description));
+    let overflow = text_length(&text).saturating_sub(max_length);
+    if overflow == 0 {
+        return text;
+    }
+    // Make room for the ellipsis too:
+    let max_description_length = text_length(description).saturating_sub(overflow + 1);
+    if max_description_length == 0 {
+        return render(None);
+    }
+    let truncated = truncate(description, max_description_length);
+    render(Some(&format!("{}…", truncated.trim_end())))
+}
+
+/// Length of the HTML's visible text, as Telegram counts it: in UTF-16 code units.
+fn text_length(html: &str) -> usize {
+    Html::parse_fragment(html).root_element().text().map(|text| text.encode_utf16().count()).sum()
+}
+
+/// Take the longest prefix of the text, which fits in the length in UTF-16 code units.
+fn truncate(text: &str, max_length: usize) -> &str {
+    let mut length = 0;
+    for (index, char) in text.char_indices() {
+        length += char.len_utf16();
+        if length > max_length {
+            return &text[..index];
+        }
+    }
+    text
 }
 
 pub struct CommandLink {
@@ -125,6 +374,12 @@ impl Render for Seller {
     }
 }
 
+impl Render for Rating {
+    fn render(&self) -> Markup {
+        html! { "⭐ " (format!("{:.1}", self.score)) " (" (self.n_reviews) ")" }
+    }
+}
+
 impl Render for Condition {
     fn render(&self) -> Markup {
         html! {
@@ -179,3 +434,39 @@ impl Render for ManageSearchQuery<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn long_item_description_truncated_ok() -> Result {
+        let item = Item::builder()
+            .id("marktplaats::m42".to_string())
+            .marketplace_id("marktplaats")
+            .url(Url::parse("https://www.marktplaats.nl/v/m42")?)
+            .title("Ubiquiti UniFi U6 Pro".to_string())
+            .description("Werkt perfect, in originele doos. ".repeat(100))
+            .price(Price::Fixed(Amount::ZERO))
+            .build();
+        let subscribe_link = CommandBuilder::new("mrktpltsbot")?.subscribe_link(42);
+        let links = [&subscribe_link];
+        let manage_search_query = ManageSearchQuery::new("unifi", &links);
+
+        let text = item_description(&item, &manage_search_query, &[], &[], 1024);
+        assert!(text_length(&text) <= 1024, "{}", text_length(&text));
+        assert!(text.contains("…</blockquote>"), "{text}");
+
+        let text = item_description(&item, &manage_search_query, &[], &[], 4096);
+        assert!(!text.contains('…'), "the description fits");
+        Ok(())
+    }
+
+    #[test]
+    fn truncate_ok() {
+        assert_eq!(truncate("unifi", 10), "unifi");
+        assert_eq!(truncate("unifi", 3), "uni");
+        assert_eq!(truncate("🔎unifi", 3), "🔎u");
+    }
+}
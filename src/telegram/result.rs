@@ -1,5 +1,6 @@
 use monostate::MustBe;
 use serde::Deserialize;
+use thiserror::Error;
 
 use crate::prelude::*;
 
@@ -10,16 +11,44 @@ use crate::prelude::*;
 #[must_use]
 #[serde(untagged)]
 pub enum TelegramResult<T> {
-    Ok { ok: MustBe!(true), result: T },
-    Err { ok: MustBe!(false), description: String, error_code: i32 },
+    Ok {
+        ok: MustBe!(true),
+        result: T,
+    },
+    Err {
+        ok: MustBe!(false),
+        description: String,
+        error_code: i32,
+        #[serde(default)]
+        parameters: ResponseParameters,
+    },
+}
+
+/// [Response parameters][1] of a failed request.
+///
+/// [1]: https://core.telegram.org/bots/api#responseparameters
+#[derive(Debug, Default, Deserialize)]
+pub struct ResponseParameters {
+    /// Number of seconds left to wait before the request can be repeated.
+    pub retry_after: Option<u32>,
+}
+
+/// Error, which Telegram has returned.
+#[derive(Debug, Error)]
+#[error("API error {error_code}: {description}")]
+pub struct ApiError {
+    pub error_code: i32,
+    pub description: String,
+    pub retry_after: Option<u32>,
 }
 
 impl<T> From<TelegramResult<T>> for Result<T> {
     fn from(result: TelegramResult<T>) -> Self {
         match result {
             TelegramResult::Ok { result, .. } => Ok(result),
-            TelegramResult::Err { error_code, description, .. } => {
-                Err(anyhow!("API error {error_code}: {description}"))
+            TelegramResult::Err { error_code, description, parameters, .. } => {
+                Err(ApiError { error_code, description, retry_after: parameters.retry_after }
+                    .into())
             }
         }
     }
@@ -41,4 +70,17 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_response_retry_after_ok() -> Result {
+        // language=json
+        let response: TelegramResult<u32> = serde_json::from_str(
+            r#"{"ok": false, "error_code": 429, "description": "Too Many Requests: retry after 5", "parameters": {"retry_after": 5}}"#,
+        )?;
+        let error = Result::from(response).unwrap_err();
+        let error = error.downcast_ref::<ApiError>().unwrap();
+        assert_eq!(error.error_code, 429);
+        assert_eq!(error.retry_after, Some(5));
+        Ok(())
+    }
 }
@@ -0,0 +1,222 @@
+//! Text bot commands like `/search@mrktpltsbot unifi`.
+
+use crate::telegram::objects::BotCommand;
+
+/// Parsed text bot command.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Command<'a> {
+    /// `/start` with an optional [deep linking][1] payload.
+    ///
+    /// [1]: https://core.telegram.org/bots/features#deep-linking
+    Start { payload: Option<&'a str> },
+
+    /// Explain how to use the bot.
+    Help,
+
+    /// Explicit search with the query in the arguments.
+    Search { query: &'a str },
+
+    /// List and manage the chat's subscriptions.
+    Manage,
+
+    /// Search the items previously notified to the chat.
+    History { text: &'a str },
+
+    /// List and unblock the chat's blocked sellers.
+    Blocked,
+
+    /// Show or change the chat's settings.
+    Settings { args: &'a str },
+
+    /// Generate an invite link (admins only).
+    Invite { args: &'a str },
+
+    /// List the authorized chats (admins only).
+    Authorized,
+
+    /// Revoke a chat authorization (admins only).
+    Revoke { args: &'a str },
+
+    /// Any command that the bot does not know.
+    Unknown {
+        name: &'a str,
+
+        /// Whether the command is explicitly addressed to the bot via the `@botname` suffix.
+        is_addressed: bool,
+    },
+}
+
+impl<'a> Command<'a> {
+    /// Commands which are registered via `setMyCommands` and shown in `/help`.
+    pub const REGISTERED: &'static [BotCommand<'static>] = &[
+        BotCommand { command: "search", description: "Search for a query and subscribe to it" },
+        BotCommand { command: "list", description: "List and manage your subscriptions" },
+        BotCommand { command: "history", description: "Search the items notified to you before" },
+        BotCommand { command: "blocked", description: "List and unblock the blocked sellers" },
+        BotCommand { command: "settings", description: "Show or change the chat settings" },
+        BotCommand { command: "help", description: "Explain how to use the bot" },
+    ];
+
+    /// Commands available to the admins only.
+    pub const ADMIN: &'static [BotCommand<'static>] = &[
+        BotCommand {
+            command: "invite",
+            description: "Generate a one-time invite, or a reusable one valid for the specified number of hours",
+        },
+        BotCommand { command: "authorized", description: "List the authorized chats" },
+        BotCommand { command: "revoke", description: "Revoke the authorization of the chat ID" },
+    ];
+
+    /// Whether the command may be intended for another bot in a group.
+    pub const fn may_be_foreign(&self) -> bool {
+        matches!(self, Self::Unknown { is_addressed: false, .. })
+    }
+
+    /// Whether the command is available to the admins only.
+    pub const fn is_admin_only(&self) -> bool {
+        matches!(self, Self::Invite { .. } | Self::Authorized | Self::Revoke { .. })
+    }
+
+    /// Parse the message text as a command.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the text is not a command, or the command is addressed to another bot
+    /// via the `@botname` suffix.
+    pub fn parse(text: &'a str, me: &str) -> Option<Self> {
+        let text = text.trim().strip_prefix('/')?;
+        let (head, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let args = args.trim();
+        let (name, is_addressed) = match head.split_once('@') {
+            Some((name, recipient)) if recipient.eq_ignore_ascii_case(me) => (name, true),
+            Some(_) => return None,
+            None => (head, false),
+        };
+        let command = match name.to_lowercase().as_str() {
+            "start" => Self::Start { payload: (!args.is_empty()).then_some(args) },
+            "help" => Self::Help,
+            "search" => Self::Search { query: args },
+            "manage" | "list" => Self::Manage,
+            "history" => Self::History { text: args },
+            "blocked" => Self::Blocked,
+            "settings" => Self::Settings { args },
+            "invite" => Self::Invite { args },
+            "authorized" => Self::Authorized,
+            "revoke" => Self::Revoke { args },
+            _ => Self::Unknown { name, is_addressed },
+        };
+        Some(command)
+    }
+}
+
+/// Strip the bot's `@username` mention from the message text.
+///
+/// # Returns
+///
+/// The rest of the text, or [`None`] if the bot is not mentioned.
+pub fn strip_mention(text: &str, me: &str) -> Option<String> {
+    let mut is_mentioned = false;
+    let rest = text
+        .split_whitespace()
+        .filter(|word| {
+            let is_mention =
+                word.strip_prefix('@').is_some_and(|username| username.eq_ignore_ascii_case(me));
+            is_mentioned |= is_mention;
+            !is_mention
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    is_mentioned.then_some(rest)
+}
+
+/// Parse an on/off setting value.
+pub fn parse_switch(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "on" | "yes" | "true" | "1" => Some(true),
+        "off" | "no" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_not_command_ok() {
+        assert_eq!(Command::parse("unifi", "mrktpltsbot"), None);
+    }
+
+    #[test]
+    fn parse_start_ok() {
+        assert_eq!(Command::parse("/start", "mrktpltsbot"), Some(Command::Start { payload: None }));
+        assert_eq!(
+            Command::parse("/start GgsJ_5xfEFkYbu0QAQ", "mrktpltsbot"),
+            Some(Command::Start { payload: Some("GgsJ_5xfEFkYbu0QAQ") }),
+        );
+    }
+
+    #[test]
+    fn parse_with_recipient_ok() {
+        assert_eq!(
+            Command::parse("/search@MrktpltsBot  unifi  u6 ", "mrktpltsbot"),
+            Some(Command::Search { query: "unifi  u6" }),
+        );
+        assert_eq!(Command::parse("/list@mrktpltsbot", "mrktpltsbot"), Some(Command::Manage));
+    }
+
+    #[test]
+    fn parse_history_ok() {
+        assert_eq!(
+            Command::parse("/history unifi u6", "mrktpltsbot"),
+            Some(Command::History { text: "unifi u6" }),
+        );
+    }
+
+    #[test]
+    fn parse_admin_ok() {
+        let command = Command::parse("/revoke -42", "mrktpltsbot").unwrap();
+        assert_eq!(command, Command::Revoke { args: "-42" });
+        assert!(command.is_admin_only());
+    }
+
+    #[test]
+    fn parse_addressed_to_another_bot_ok() {
+        assert_eq!(Command::parse("/help@otherbot", "mrktpltsbot"), None);
+    }
+
+    #[test]
+    fn parse_unknown_ok() {
+        assert_eq!(
+            Command::parse("/foo bar", "mrktpltsbot"),
+            Some(Command::Unknown { name: "foo", is_addressed: false }),
+        );
+    }
+
+    #[test]
+    fn parse_unknown_addressed_ok() {
+        let command = Command::parse("/foo@MrktpltsBot bar", "mrktpltsbot").unwrap();
+        assert_eq!(command, Command::Unknown { name: "foo", is_addressed: true });
+        assert!(!command.may_be_foreign());
+        assert!(Command::parse("/foo bar", "mrktpltsbot").unwrap().may_be_foreign());
+        assert!(!Command::parse("/help", "mrktpltsbot").unwrap().may_be_foreign());
+    }
+
+    #[test]
+    fn strip_mention_ok() {
+        assert_eq!(
+            strip_mention("@MrktpltsBot unifi u6", "mrktpltsbot").as_deref(),
+            Some("unifi u6")
+        );
+        assert_eq!(strip_mention("unifi @mrktpltsbot", "mrktpltsbot").as_deref(), Some("unifi"));
+        assert_eq!(strip_mention("unifi @otherbot", "mrktpltsbot"), None);
+        assert_eq!(strip_mention("unifi", "mrktpltsbot"), None);
+    }
+
+    #[test]
+    fn parse_switch_ok() {
+        assert_eq!(parse_switch("ON"), Some(true));
+        assert_eq!(parse_switch("off"), Some(false));
+        assert_eq!(parse_switch("maybe"), None);
+    }
+}
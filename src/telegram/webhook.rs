@@ -0,0 +1,141 @@
+//! Embedded HTTP server which receives [`Update`]'s via a [webhook][1].
+//!
+//! [1]: https://core.telegram.org/bots/api#setwebhook
+
+use std::net::SocketAddr;
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+};
+use secrecy::{ExposeSecret, SecretString};
+use subtle::ConstantTimeEq;
+use tokio::{net::TcpListener, sync::mpsc};
+use url::Url;
+
+use crate::{prelude::*, telegram::objects::Update};
+
+/// Header which Telegram sets to the secret token in every webhook request.
+const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+/// Webhook settings.
+pub struct Webhook {
+    /// Public URL, which Telegram sends the updates to.
+    ///
+    /// The server listens on the URL's path.
+    pub url: Url,
+
+    /// Socket address to listen on.
+    pub bind_address: SocketAddr,
+
+    pub secret_token: SecretString,
+}
+
+impl Webhook {
+    /// Generate a random secret token.
+    pub fn generate_secret_token() -> SecretString {
+        base64_url::encode(&rand::random::<[u8; 32]>()).into()
+    }
+
+    /// Bind the server and serve it in the background.
+    ///
+    /// # Returns
+    ///
+    /// Receiver of the incoming updates.
+    #[instrument(skip_all, fields(bind_address = %self.bind_address))]
+    pub async fn spawn(&self) -> Result<mpsc::Receiver<Update>> {
+        let listener = TcpListener::bind(self.bind_address)
+            .await
+            .with_context(|| format!("failed to bind `{}`", self.bind_address))?;
+        let (sender, receiver) = mpsc::channel(100);
+        let router = router(self.url.path(), self.secret_token.clone(), sender);
+        info!(path = self.url.path(), "🕸️ Serving the webhook…");
+        tokio::spawn(async move {
+            if let Err(error) = axum::serve(listener, router).await {
+                error!("‼️ The webhook server has failed: {error:#}");
+            }
+        });
+        Ok(receiver)
+    }
+}
+
+#[derive(Clone)]
+struct WebhookState {
+    secret_token: SecretString,
+    updates: mpsc::Sender<Update>,
+}
+
+fn router(path: &str, secret_token: SecretString, updates: mpsc::Sender<Update>) -> Router {
+    Router::new().route(path, post(on_update)).with_state(WebhookState { secret_token, updates })
+}
+
+async fn on_update(
+    State(state): State<WebhookState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    // Compare in constant time, so that the token cannot be guessed by the response timing:
+    let is_authorized = headers.get(SECRET_TOKEN_HEADER).is_some_and(|secret_token| {
+        secret_token.as_bytes().ct_eq(state.secret_token.expose_secret().as_bytes()).into()
+    });
+    if !is_authorized {
+        warn!("⚠️ Received a webhook request with an invalid secret token");
+        return StatusCode::UNAUTHORIZED;
+    }
+    let update: Update = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(error) => {
+            warn!("⚠️ Failed to deserialize the update: {error:#}");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    debug!(update.id, "📬 Received Telegram update");
+    if state.updates.send(update).await.is_err() {
+        error!("‼️ The update receiver is closed");
+        return StatusCode::SERVICE_UNAVAILABLE;
+    }
+    StatusCode::OK
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::telegram::objects::UpdatePayload;
+
+    #[tokio::test]
+    async fn post_update_ok() -> Result {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let address = listener.local_addr()?;
+        let (sender, mut receiver) = mpsc::channel(1);
+        let router = router("/webhook", "secret".into(), sender);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let url = format!("http://{address}/webhook");
+        // language=json
+        let update = r#"{"update_id": 42, "message": {"message_id": 43, "text": "unifi", "chat": {"id": 44, "type": "private"}}}"#;
+        let client = reqwest::Client::new();
+
+        let response = client.post(&url).body(update).send().await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response =
+            client.post(&url).header(SECRET_TOKEN_HEADER, "secreT").body(update).send().await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response =
+            client.post(&url).header(SECRET_TOKEN_HEADER, "secret").body(update).send().await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let update = receiver.recv().await.unwrap();
+        assert_eq!(update.id, 42);
+        let UpdatePayload::Message(message) = update.payload else { unreachable!() };
+        assert_eq!(message.text.as_deref(), Some("unifi"));
+
+        Ok(())
+    }
+}